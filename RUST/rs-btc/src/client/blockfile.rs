@@ -0,0 +1,233 @@
+use bitcoincore_rpc::bitcoin::{
+    consensus::deserialize, p2p::Magic, Amount, Block, BlockHash, Network,
+};
+use serde::Serialize;
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Length of the obfuscation key bitcoind stores in `blocks/xor.dat`.
+const XOR_KEY_LEN: usize = 8;
+
+#[derive(Debug, Serialize)]
+pub struct BlockFileStats {
+    pub file: String,
+    pub blockhash: BlockHash,
+    pub prev_blockhash: BlockHash,
+    pub time: u32,
+    pub txs: usize,
+    pub ins: usize,
+    pub outs: usize,
+    pub total_size: usize,
+    pub total_weight: u64,
+    pub total_out: Amount,
+}
+
+impl BlockFileStats {
+    fn new(file: &Path, block: &Block) -> BlockFileStats {
+        let non_coinbase = block.txdata.iter().skip(1);
+
+        BlockFileStats {
+            file: file.display().to_string(),
+            blockhash: block.block_hash(),
+            prev_blockhash: block.header.prev_blockhash,
+            time: block.header.time,
+            txs: block.txdata.len(),
+            ins: non_coinbase.map(|tx| tx.input.len()).sum(),
+            outs: block.txdata.iter().map(|tx| tx.output.len()).sum(),
+            total_size: block.total_size(),
+            total_weight: block.weight().to_wu(),
+            total_out: block
+                .txdata
+                .iter()
+                .flat_map(|tx| tx.output.iter())
+                .map(|output| output.value)
+                .sum(),
+        }
+    }
+}
+
+/// Reads blocks straight from bitcoind `blk*.dat` files, in file order.
+///
+/// Every record is framed as `<magic><u32 le size><block>`. Since v28 the
+/// files may be XOR-obfuscated with the key found in `xor.dat`.
+pub struct BlockFileReader {
+    files: Vec<PathBuf>,
+    magic: Magic,
+    xor_key: Option<[u8; XOR_KEY_LEN]>,
+}
+
+impl BlockFileReader {
+    pub fn open(blocks_dir: &str, network: Network) -> Result<BlockFileReader, Box<dyn Error>> {
+        let blocks_dir = Path::new(blocks_dir);
+
+        let mut files: Vec<PathBuf> = fs::read_dir(blocks_dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with("blk") && name.ends_with(".dat"))
+            })
+            .collect();
+        files.sort();
+
+        let xor_key = match fs::read(blocks_dir.join("xor.dat")) {
+            Ok(bytes) if bytes.len() == XOR_KEY_LEN && bytes.iter().any(|b| *b != 0) => {
+                let mut key = [0u8; XOR_KEY_LEN];
+                key.copy_from_slice(&bytes);
+                Some(key)
+            }
+            _ => None,
+        };
+
+        Ok(BlockFileReader {
+            files,
+            magic: network.magic(),
+            xor_key,
+        })
+    }
+
+    pub fn read_file(&self, path: &Path) -> Result<Vec<Block>, Box<dyn Error>> {
+        let mut data = fs::read(path)?;
+
+        if let Some(key) = self.xor_key {
+            for (i, byte) in data.iter_mut().enumerate() {
+                *byte ^= key[i % XOR_KEY_LEN];
+            }
+        }
+
+        let mut blocks = Vec::new();
+        let mut offset = 0;
+
+        while offset + 8 <= data.len() {
+            let magic = Magic::from_bytes(data[offset..offset + 4].try_into()?);
+
+            // bitcoind pre-allocates block files, the zeroed tail marks the end.
+            if magic.to_bytes() == [0u8; 4] {
+                break;
+            }
+            if magic != self.magic {
+                return Err(format!(
+                    "unexpected magic {} at offset {} in {}",
+                    magic,
+                    offset,
+                    path.display()
+                )
+                .into());
+            }
+
+            let size = u32::from_le_bytes(data[offset + 4..offset + 8].try_into()?) as usize;
+            let start = offset + 8;
+            let end = start + size;
+
+            if end > data.len() {
+                return Err(
+                    format!("truncated block at offset {} in {}", offset, path.display()).into(),
+                );
+            }
+
+            blocks.push(deserialize::<Block>(&data[start..end])?);
+            offset = end;
+        }
+
+        Ok(blocks)
+    }
+
+    pub fn for_each_block<F>(&self, limit: Option<usize>, mut f: F) -> Result<usize, Box<dyn Error>>
+    where
+        F: FnMut(&Path, Block),
+    {
+        let mut count = 0;
+
+        for path in self.files.iter() {
+            for block in self.read_file(path)? {
+                if limit.is_some_and(|limit| count >= limit) {
+                    return Ok(count);
+                }
+
+                f(path, block);
+                count += 1;
+            }
+        }
+
+        Ok(count)
+    }
+
+    pub fn block_stats(&self, limit: Option<usize>) -> Result<Vec<BlockFileStats>, Box<dyn Error>> {
+        let mut stats = Vec::new();
+        self.for_each_block(limit, |path, block| {
+            stats.push(BlockFileStats::new(path, &block))
+        })?;
+
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoincore_rpc::bitcoin::{blockdata::constants::genesis_block, consensus::serialize};
+
+    /// Fresh blocks directory with `blk00000.dat` holding `data`, obfuscated with `key`.
+    fn blocks_dir(name: &str, data: &[u8], key: Option<[u8; XOR_KEY_LEN]>) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rs-btc-{}-{name}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut data = data.to_vec();
+        if let Some(key) = key {
+            fs::write(dir.join("xor.dat"), key).unwrap();
+            for (i, byte) in data.iter_mut().enumerate() {
+                *byte ^= key[i % XOR_KEY_LEN];
+            }
+        }
+        fs::write(dir.join("blk00000.dat"), data).unwrap();
+        dir
+    }
+
+    fn record(block: &Block) -> Vec<u8> {
+        let bytes = serialize(block);
+        let mut record = Network::Bitcoin.magic().to_bytes().to_vec();
+        record.extend((bytes.len() as u32).to_le_bytes());
+        record.extend(bytes);
+        record
+    }
+
+    #[test]
+    fn reads_obfuscated_blocks() {
+        let genesis = genesis_block(Network::Bitcoin);
+        let mut data = record(&genesis);
+        data.extend(record(&genesis));
+        // Pre-allocated, zeroed tail.
+        data.extend([0u8; 64]);
+
+        let key = [0x5a, 0x13, 0x00, 0xff, 0x81, 0x7c, 0x22, 0xe9];
+        let dir = blocks_dir("xor", &data, Some(key));
+        let reader = BlockFileReader::open(dir.to_str().unwrap(), Network::Bitcoin);
+        let blocks = reader.and_then(|reader| reader.read_file(&dir.join("blk00000.dat")));
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(blocks.unwrap(), [genesis.clone(), genesis]);
+    }
+
+    #[test]
+    fn reports_a_truncated_record() {
+        let genesis = genesis_block(Network::Bitcoin);
+        let mut data = record(&genesis);
+        let partial = record(&genesis);
+        data.extend(&partial[..partial.len() - 10]);
+
+        let dir = blocks_dir("truncated", &data, None);
+        let reader = BlockFileReader::open(dir.to_str().unwrap(), Network::Bitcoin).unwrap();
+        let result = reader.read_file(&dir.join("blk00000.dat"));
+        let mut count = 0;
+        let all = reader.for_each_block(None, |_, _| count += 1);
+        fs::remove_dir_all(&dir).unwrap();
+
+        let err = result.unwrap_err().to_string();
+        assert!(err.starts_with("truncated block at offset 293 "), "{err}");
+        assert!(all.is_err());
+        assert_eq!(count, 0);
+    }
+}
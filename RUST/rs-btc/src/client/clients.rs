@@ -1,15 +1,28 @@
 use crate::client::{
+    blockfile::BlockFileReader,
+    filter::{FilterMatch, FilterScanner},
+    history::HistoryExporter,
+    message,
+    miner::{self, TemplateSummary},
+    repl::{Command, Repl},
     selection::Selector,
-    utils::{get_address, get_block_number, get_bool, get_string_array, prettify_data},
+    session::Session,
+    utils::{
+        get_address, get_block_number, get_bool, get_script_array, get_string_array, parse_address,
+        prettify_data, print_plain, set_pending_input, take_input,
+    },
+    utxo::{self, UtxoReport, UtxoScan, UtxoSetSummary},
+    wallet::{self, BackupSchedule, WalletOverview},
 };
 
 use bitcoincore_rpc::{
     bitcoin::{
         address::{NetworkChecked, NetworkUnchecked},
         block::Header,
+        consensus::encode::serialize_hex,
         secp256k1::ecdsa::Signature,
-        Address, Amount, Block, BlockHash, OutPoint, PrivateKey, PublicKey, Script, Transaction,
-        Txid,
+        Address, Amount, Block, BlockHash, Network, OutPoint, PrivateKey, PublicKey, Script,
+        ScriptBuf, Transaction, Txid,
     },
     json::{
         AddMultiSigAddressResult, AddressType, BlockRef, BlockStatsFields,
@@ -31,12 +44,21 @@ use bitcoincore_rpc::{
     },
     Auth, Client, RawTx, RpcApi,
 };
-use std::{collections::HashMap, error::Error};
+use std::{collections::HashMap, error::Error, time::Duration};
 
 use super::utils::{get_node_address, get_txid_array, print_object};
 
+/// Request timeout for calls such as `scantxoutset` that walk the whole UTXO set.
+const LONG_RUNNING_TIMEOUT: Duration = Duration::from_secs(3600);
+
 pub struct Clients<'a> {
     rpc: Client,
+    rpc_url: String,
+    auth: Auth,
+    active_wallet: Option<String>,
+    utxo_scan: Option<UtxoScan>,
+    backups: Option<BackupSchedule>,
+    session: Session,
     selector: Selector<'a>,
 }
 
@@ -45,8 +67,10 @@ impl<'a> Clients<'a> {
         rpc_url: &str,
         rpc_user: String,
         rpc_password: String,
+        session: Session,
     ) -> Result<Clients, Box<dyn Error>> {
-        let rpc = Client::new(rpc_url, Auth::UserPass(rpc_user, rpc_password))?;
+        let auth = Auth::UserPass(rpc_user, rpc_password);
+        let rpc = session.client(rpc_url, &auth, None)?;
         let mut selector = Selector::new();
 
         selector.insert_block_kv();
@@ -56,13 +80,41 @@ impl<'a> Clients<'a> {
         selector.insert_network_kv();
         selector.insert_utils_kv();
         selector.insert_psbt_kv();
-        selector.get_keys();
-
-        Ok(Clients { rpc, selector })
+        selector.insert_offline_kv();
+        selector.insert_params();
+
+        Ok(Clients {
+            rpc,
+            rpc_url: rpc_url.trim_end_matches('/').to_string(),
+            auth,
+            active_wallet: None,
+            utxo_scan: None,
+            backups: None,
+            session,
+            selector,
+        })
+    }
+
+    /// Client bound to `<url>/wallet/<name>`, needed once several wallets are loaded.
+    fn wallet_client(&self, wallet: &str) -> Result<Client, Box<dyn Error>> {
+        let url = format!("{}/wallet/{}", self.rpc_url, wallet::url_segment(wallet));
+        let rpc = self.session.client(&url, &self.auth, None)?;
+        Ok(rpc)
+    }
+
+    /// Sends every following command to `wallet`, or to the node endpoint for `None`.
+    fn use_wallet(&mut self, wallet: Option<&str>) -> Result<(), Box<dyn Error>> {
+        self.rpc = match wallet {
+            Some(wallet) => self.wallet_client(wallet)?,
+            None => self.session.client(&self.rpc_url, &self.auth, None)?,
+        };
+        self.active_wallet = wallet.map(|wallet| wallet.to_string());
+        Ok(())
     }
 
-    fn get_block_count(&self) -> u64 {
-        self.rpc.get_block_count().unwrap()
+    fn get_block_count(&self) -> Result<u64, Box<dyn Error>> {
+        let block_count = self.rpc.get_block_count()?;
+        Ok(block_count)
     }
 
     fn get_latest_block_hash(&self) -> Result<BlockHash, Box<dyn Error>> {
@@ -102,7 +154,10 @@ impl<'a> Clients<'a> {
     }
 
     fn unload_wallet(&self, wallet: Option<&str>) -> Result<UnloadWalletResult, Box<dyn Error>> {
-        let wallet = self.rpc.unload_wallet(wallet)?.unwrap();
+        let wallet = self
+            .rpc
+            .unload_wallet(wallet)?
+            .ok_or("unloadwallet returned no result")?;
         Ok(wallet)
     }
 
@@ -447,17 +502,68 @@ impl<'a> Clients<'a> {
     fn create_wallet(
         &self,
         wallet: &str,
-        disable_private_keys: Option<bool>,
-        blank: Option<bool>,
+        disable_private_keys: bool,
+        blank: bool,
         passphrase: Option<&str>,
-        avoid_reuse: Option<bool>,
+        avoid_reuse: bool,
+        descriptors: bool,
     ) -> Result<LoadWalletResult, Box<dyn Error>> {
-        let wallet =
-            self.rpc
-                .create_wallet(wallet, disable_private_keys, blank, passphrase, avoid_reuse)?;
+        // The library wrapper has no descriptors flag, so build the call by hand.
+        let args: [serde_json::Value; 6] = [
+            wallet.into(),
+            disable_private_keys.into(),
+            blank.into(),
+            passphrase.into(),
+            avoid_reuse.into(),
+            descriptors.into(),
+        ];
+        let wallet = self.rpc.call("createwallet", &args)?;
         Ok(wallet)
     }
 
+    fn wallet_overview(&self) -> Result<Vec<WalletOverview>, Box<dyn Error>> {
+        let mut overview = Vec::new();
+        for wallet in self.list_wallets()? {
+            let rpc = self.wallet_client(&wallet)?;
+            overview.push(WalletOverview::new(&wallet, &rpc)?);
+        }
+        Ok(overview)
+    }
+
+    /// Backs up every loaded wallet into `dir` in the background, keeping the
+    /// newest `keep` per wallet. Wallets loaded later are not included.
+    fn schedule_backups(
+        &mut self,
+        dir: &str,
+        interval: Duration,
+        keep: usize,
+        rounds: usize,
+    ) -> Result<usize, Box<dyn Error>> {
+        if self
+            .backups
+            .as_ref()
+            .is_some_and(|backups| !backups.is_finished())
+        {
+            return Err("backups are already scheduled, see backup_status".into());
+        }
+
+        let mut wallets = Vec::new();
+        for wallet in self.list_wallets()? {
+            let rpc = self.wallet_client(&wallet)?;
+            wallets.push((wallet, rpc));
+        }
+        let count = wallets.len();
+
+        self.backups = Some(BackupSchedule::start(
+            wallets,
+            dir.to_string(),
+            interval,
+            keep,
+            rounds,
+        ));
+        Ok(count)
+    }
+
     fn get_block_header_info(
         &self,
         hash: &BlockHash,
@@ -476,6 +582,21 @@ impl<'a> Clients<'a> {
         Ok(template)
     }
 
+    fn get_segwit_block_template(&self) -> Result<GetBlockTemplateResult, Box<dyn Error>> {
+        let template = self.get_block_template(
+            GetBlockTemplateModes::Template,
+            &[GetBlockTemplateRules::SegWit],
+            &[],
+        )?;
+        Ok(template)
+    }
+
+    fn submit_mined_block(&self, block: &Block) -> Result<Option<String>, Box<dyn Error>> {
+        let block_hex = serialize_hex(block);
+        let rejection = self.rpc.call("submitblock", &[block_hex.into()])?;
+        Ok(rejection)
+    }
+
     fn get_block_stats_fields(
         &self,
         height: u64,
@@ -771,6 +892,13 @@ impl<'a> Clients<'a> {
         Ok(is_verified)
     }
 
+    fn sign_message(&self, address: &Address, message: &str) -> Result<String, Box<dyn Error>> {
+        let signature = self
+            .rpc
+            .call("signmessage", &[address.to_string().into(), message.into()])?;
+        Ok(signature)
+    }
+
     fn get_new_address(
         &self,
         label: Option<&str>,
@@ -892,13 +1020,76 @@ impl<'a> Clients<'a> {
         let tx_out = self.rpc.scan_tx_out_set_blocking(descriptors)?;
         Ok(tx_out)
     }
+
+    /// Node client without the default 15 second request timeout.
+    fn long_running_client(&self) -> Result<Client, Box<dyn Error>> {
+        let rpc = self
+            .session
+            .client(&self.rpc_url, &self.auth, Some(LONG_RUNNING_TIMEOUT))?;
+        Ok(rpc)
+    }
+
+    fn start_utxo_scan(&mut self, requests: Vec<ScanTxOutRequest>) -> Result<(), Box<dyn Error>> {
+        if self
+            .utxo_scan
+            .as_ref()
+            .is_some_and(|scan| !scan.is_finished())
+        {
+            return Err("a UTXO scan is already running, see scan_status".into());
+        }
+
+        let rpc = self.long_running_client()?;
+        self.utxo_scan = Some(UtxoScan::start(rpc, requests));
+        Ok(())
+    }
+
+    /// Returns the report once the scan is done, otherwise prints its progress.
+    fn poll_utxo_scan(&mut self) -> Result<Option<UtxoReport>, Box<dyn Error>> {
+        let Some(scan) = self.utxo_scan.take() else {
+            return Err("no UTXO scan was started".into());
+        };
+
+        if scan.is_finished() {
+            return Ok(Some(UtxoReport::new(scan.join()?)));
+        }
+
+        match utxo::scan_progress(&self.rpc)? {
+            Some(progress) => println!(
+                "Scanning UTXO set: {}% after {}s",
+                progress,
+                scan.elapsed().as_secs()
+            ),
+            None => println!("Waiting for the scan to start"),
+        }
+        self.utxo_scan = Some(scan);
+        Ok(None)
+    }
+
+    fn scan_block_filters(
+        &self,
+        start: u64,
+        stop: u64,
+        scripts: Vec<ScriptBuf>,
+    ) -> Result<Vec<FilterMatch>, Box<dyn Error>> {
+        let mut scanner = FilterScanner::new(&self.rpc, scripts);
+        let matches = scanner.scan(start, stop)?;
+        Ok(matches)
+    }
 }
 
-fn take_input(message: &str) -> String {
-    println!("Please enter {message} : ");
-    let mut input = String::new();
-    std::io::stdin().read_line(&mut input).unwrap();
-    input.trim().to_string()
+fn get_block_file_reader() -> Result<BlockFileReader, Box<dyn Error>> {
+    let blocks_dir = take_input("blocks directory");
+    let network = take_input("network (main, test, signet, regtest)");
+    let network = Network::from_core_arg(network.trim())?;
+
+    BlockFileReader::open(blocks_dir.trim(), network)
+}
+
+fn get_block_limit() -> Result<Option<usize>, Box<dyn Error>> {
+    match take_input("number of blocks (0 for all)").parse()? {
+        0 => Ok(None),
+        limit => Ok(Some(limit)),
+    }
 }
 
 pub fn run() -> Result<(), Box<dyn Error>> {
@@ -911,502 +1102,652 @@ pub fn run() -> Result<(), Box<dyn Error>> {
     //     rpc_user,     // "abc".to_string(),
     //     rpc_password, // "abc".to_string(),
     // )?;
-    let client = Clients::new(
+    let session = Session::from_args(std::env::args().skip(1))?;
+    let mut client = Clients::new(
         "http://localhost:8332",
         "abc".to_string(),
         "abc".to_string(),
+        session,
     )?;
 
+    let mut repl = Repl::new(&client.selector)?;
+    println!("Type help to list commands, tab to complete");
+
     loop {
-        client.selector.print_all_keys();
-        let key = take_input("Type ").to_lowercase();
+        let (function_name, args) = match repl.read_command(&client.selector)? {
+            Command::Call(function_name, args) => (function_name, args),
+            Command::Exit => break,
+            Command::Empty => continue,
+        };
 
-        if !client.selector.get_keys().contains(&key.trim()) {
-            println!("\ninvalid type {}", key);
-            println!("\nSelect type from");
-            continue;
+        set_pending_input(args);
+        if let Err(err) = dispatch(&mut client, &function_name) {
+            println!("Error: {}", err);
         }
+        set_pending_input(Vec::new());
+        repl.set_wallet(client.active_wallet.as_deref());
+    }
 
-        client.selector.print_with_key(key.trim());
-        let value = client.selector.get_values(key.trim());
-        let user_selected_function = take_input("function you want to work with ").to_lowercase();
-        let function_name = match value {
-            Some(value) => {
-                if !value
-                    .iter()
-                    .any(|&s| s.contains(user_selected_function.trim()))
-                {
-                    // if !value.contains(&user_selected_function.trim()) {
-                    println!("\ninvalid function {}\n", user_selected_function);
-                    continue;
-                }
-                user_selected_function.trim()
-            }
-            None => unreachable!(),
-        };
+    Ok(())
+}
 
-        if function_name == "get_block_count" {
-            let data = client.get_block_count();
-            println!("Current Block Count : {}", data);
-        } else if function_name == "get_latest_block_hash" {
-            let data = client.get_latest_block_hash().unwrap();
-            println!("Current Block Hash : {}", data);
-        } else if function_name == "get_block" {
-            let height = get_block_number();
-            let block_hash = client.get_block_hash(height).unwrap();
-            let block = client.get_block(block_hash).unwrap();
-            prettify_data(block);
-        } else if function_name == "get_best_block" {
-            let block = client.get_best_block().unwrap();
-            prettify_data(block);
-        } else if function_name == "get_network_info" {
-            let data = client.get_network_info().unwrap();
-            prettify_data(data);
-        } else if function_name == "get_index_info" {
-            let data = client.get_index_info().unwrap();
-            prettify_data(data);
-        } else if function_name == "version" {
-            let data = client.version().unwrap();
-            prettify_data(data);
-        // } else if function_name == "load_wallet" {
-        //     let data = client.load_wallet().unwrap();
-        // } else if function_name == "unload_wallet" {
-        //     let data = client.unload_wallet().unwrap();
-        } else if function_name == "list_wallets" {
-            let data = client.list_wallets().unwrap();
-            for wallet in data.iter() {
-                println!("{}", wallet);
-            }
-        } else if function_name == "list_wallet_dir" {
-            let data = client.list_wallet_dir().unwrap();
-            for wallet_dir in data.iter() {
-                println!("{}", wallet_dir);
-            }
-        } else if function_name == "get_wallet_info" {
-            let data = client.get_wallet_info().unwrap();
-            prettify_data(data);
-        // } else if function_name == "backup_wallet" {
-        //     let data = client.backup_wallet().unwrap();
-        } else if function_name == "dump_private_key" {
-            let address = get_address();
-            let p_key = client.dump_private_key(address).unwrap();
-            println!("{}", p_key);
-        } else if function_name == "encrypt_wallet" {
-            let address = take_input("passphrase");
-            client.encrypt_wallet(&address).unwrap();
-        } else if function_name == "get_difficulty" {
-            let data = client.get_difficulty().unwrap();
-            println!("{}", data);
-        } else if function_name == "get_connection_count" {
-            let data = client.get_connection_count().unwrap();
-            println!("{}", data);
-        } else if function_name == "get_block_hex" {
-            let height = get_block_number();
-            let block_hash = client.get_block_hash(height).unwrap();
-            let data = client.get_block_hex(block_hash).unwrap();
-            println!("{}", data);
-        } else if function_name == "get_block_info" {
-            let height = get_block_number();
-            let block_hash = client.get_block_hash(height).unwrap();
-            let block = client.get_block_info(block_hash).unwrap();
-            prettify_data(block);
-        } else if function_name == "get_block_header" {
-            let height = get_block_number();
-            let block_hash = client.get_block_hash(height).unwrap();
-            let block = client.get_block_header(block_hash).unwrap();
-            prettify_data(block);
-        } else if function_name == "get_mining_info" {
-            let data = client.get_mining_info().unwrap();
-            prettify_data(data);
-        } else if function_name == "get_blockchain_info" {
-            let data = client.get_blockchain_info().unwrap();
-            prettify_data(data);
-        } else if function_name == "get_best_block_hash" {
-            let data = client.get_best_block_hash().unwrap();
-            println!("{}", data);
-        } else if function_name == "get_block_hash" {
-            let height = get_block_number();
-            let data = client.get_block_hash(height).unwrap();
-            println!("{}", data);
-        } else if function_name == "get_block_stats" {
-            let height = get_block_number();
-            let data = client.get_block_stats(height).unwrap();
-            prettify_data(data);
-        } else if function_name == "get_balances" {
-            let data = client.get_balances().unwrap();
-            prettify_data(data);
-        } else if function_name == "get_received_by_address" {
-            let address = get_address();
-            let min_conf = take_input("minimum confirmation").parse().unwrap();
-
-            let data = client
-                .get_received_by_address(&address, Some(min_conf))
-                .unwrap();
-            println!("{}", data);
-        } else if function_name == "set_label" {
-            let address = get_address();
-            let label = take_input("label");
-
-            client.set_label(&address, label.trim()).unwrap();
-        } else if function_name == "key_pool_refill" {
-            let size = take_input("new_size").parse().unwrap();
-            client.key_pool_refill(Some(size)).unwrap();
-        // } else if function_name == "lock_unspent" {
-        //     let data = client.lock_unspent().unwrap();
-        //     println!("{}", data);
-        // } else if function_name == "unlock_unspent" {
-        //     let data = client.unlock_unspent().unwrap();
-        //     println!("{}", data);
-        } else if function_name == "unlock_unspent_all" {
-            let data = client.unlock_unspent_all().unwrap();
-            println!("{}", data);
-        } else if function_name == "stop" {
-            client.stop().unwrap();
-        // } else if function_name == "get_raw_change_address" {
-        //     let data = client.get_raw_change_address().unwrap();
-        //     println!("{}", data);
-        } else if function_name == "get_address_info" {
-            let address = get_address();
-            let data = client.get_address_info(&address).unwrap();
-            prettify_data(data);
-        } else if function_name == "generate" {
-            let block_number = take_input("block number").parse().unwrap();
-            let max_tries = take_input("max tries").parse().unwrap();
-            let data = client.generate(block_number, Some(max_tries)).unwrap();
-            print_object(data);
-        } else if function_name == "invalidate_block" {
-            let height = get_block_number();
-            let block_hash = client.get_block_hash(height).unwrap();
-            client.invalidate_block(block_hash).unwrap();
-        } else if function_name == "reconsider_block" {
-            let height = get_block_number();
-            let block_hash = client.get_block_hash(height).unwrap();
-            client.reconsider_block(block_hash).unwrap();
-        } else if function_name == "get_mempool_entry" {
-            let tx_id = take_input("transaction id ").parse().unwrap();
-            let data = client.get_mempool_entry(tx_id).unwrap();
-            prettify_data(data);
-        } else if function_name == "get_raw_mempool" {
-            let data = client.get_raw_mempool().unwrap();
-            print_object(data);
-        } else if function_name == "get_chain_tips" {
-            let data = client.get_chain_tips().unwrap();
-            print_object(data);
-        } else if function_name == "add_node" {
-            let node_address = get_node_address();
-
-            client.add_node(&node_address).unwrap();
-        } else if function_name == "remove_node" {
-            let node_address = get_node_address();
-            client.remove_node(&node_address).unwrap();
-        } else if function_name == "onetry_node" {
-            let node_address = get_node_address();
-            client.onetry_node(&node_address).unwrap();
-        } else if function_name == "disconnect_node" {
-            let node_address = get_node_address();
-            client.disconnect_node(&node_address).unwrap();
-        } else if function_name == "disconnect_node_by_id" {
-            let node_id = take_input("node id ").parse().unwrap();
-
-            client.disconnect_node_by_id(node_id).unwrap();
-        } else if function_name == "get_added_node_info" {
-            let node = take_input("node ");
-            let data = client.get_added_node_info(Some(&node)).unwrap();
-            print_object(data);
-        } else if function_name == "list_banned" {
-            let data = client.list_banned().unwrap();
-            print_object(data);
-        } else if function_name == "clear_banned" {
-            client.clear_banned().unwrap();
-        // } else if function_name == "add_ban" {
-        //     // subnet: &str, bantime: u64, absolute: bool
-        //     client.add_ban().unwrap();
-        // } else if function_name == "remove_ban" {
-        //     client.remove_ban().unwrap();
-        } else if function_name == "set_network_active" {
-            let input = get_bool("\n0. for false \n1. for true");
-            let data = client.set_network_active(input).unwrap();
-            println!("{}", data);
-        } else if function_name == "get_peer_info" {
-            let data = client.get_peer_info().unwrap();
-            print_object(data);
-        } else if function_name == "ping" {
-            client.ping().unwrap();
-        // } else if function_name == "send_raw_transaction" {
-        //     let data = client.send_raw_transaction().unwrap();
-        //     println!("{}", data);
-        } else if function_name == "wait_for_new_block" {
-            let height = get_block_number();
-            let block_hash = client.get_block_hash(height).unwrap();
-            let timeout = take_input("time out").parse().unwrap();
-            let data = client.wait_for_new_block(block_hash, timeout).unwrap();
-            prettify_data(data);
-        } else if function_name == "get_descriptor_info" {
-            let input = take_input("desc");
-            let data = client.get_descriptor_info(&input).unwrap();
-            prettify_data(data);
-        } else if function_name == "join_psbt" {
-            let data = get_string_array("psbt array");
-            let data = client.join_psbt(&data).unwrap();
-            println!("{}", data);
-        } else if function_name == "combine_psbt" {
-            let data = get_string_array("psbt array");
-            let data = client.combine_psbt(&data).unwrap();
-            println!("{}", data);
-        } else if function_name == "combine_raw_transaction" {
-            let data = get_string_array("raw transaction array");
-            let data = client.combine_raw_transaction(&data).unwrap();
-            println!("{}", data);
-        } else if function_name == "finalize_psbt" {
-            let psbt = take_input("psbt");
-            let input = get_bool("do you want to extract?");
-            let data = client.finalize_psbt(&psbt, Some(input)).unwrap();
-            prettify_data(data);
-        // } else if function_name == "derive_addresses" {
-        //     let data = client.derive_addresses().unwrap();
-        //     print_object(data);
-        } else if function_name == "get_net_totals" {
-            let data = client.get_net_totals().unwrap();
-            prettify_data(data);
-        } else if function_name == "get_network_hash_ps" {
-            let nblocks = take_input("psbt").parse().unwrap();
-            let height = take_input("psbt").parse().unwrap();
-            let data = client
-                .get_network_hash_ps(Some(nblocks), Some(height))
-                .unwrap();
-            println!("{}", data);
-        } else if function_name == "uptime" {
-            let data = client.uptime().unwrap();
-            println!("{}", data);
-        // } else if function_name == "submit_block" {
-        //     client.submit_block().unwrap();
-        // } else if function_name == "submit_block_bytes" {
-        //     client.submit_block_bytes().unwrap();
-        } else if function_name == "submit_block_hex" {
-            let nblocks = take_input("block hex");
-
-            client.submit_block_hex(nblocks.trim()).unwrap();
-        // } else if function_name == "add_multisig_address" {
-        //     let data = client.add_multisig_address().unwrap();
-        //     prettify_data(data);
-        } else if function_name == "create_wallet" {
-            let wallet = take_input("wallet");
-            let disable_private_keys = get_bool("disable private keys");
-            let blank = get_bool("blank");
-            let passphrase = take_input("passphrase");
-            let avoid_reuse = get_bool("avoid reuse");
-
-            let data = client
-                .create_wallet(
-                    wallet.trim(),
-                    Some(disable_private_keys),
-                    Some(blank),
-                    Some(passphrase.trim()),
-                    Some(avoid_reuse),
-                )
-                .unwrap();
-            prettify_data(data);
-        } else if function_name == "get_block_header_info" {
-            let height = get_block_number();
-            let block_hash = client.get_block_hash(height).unwrap();
-            let data = client.get_block_header_info(&block_hash).unwrap();
-            prettify_data(data);
-        // } else if function_name == "get_block_template" {
-        //     let data = client.get_block_template().unwrap();
-        //     prettify_data(data);
-        // } else if function_name == "get_block_stats_fields" {
-        //     let data = client.get_block_stats_fields().unwrap();
-        //     prettify_data(data);
-        } else if function_name == "get_raw_transaction" {
-            let tx_id = take_input("transaction id ").parse().unwrap();
-            let height = get_block_number();
-            let block_hash = client.get_block_hash(height).unwrap();
-            let data = client
-                .get_raw_transaction(&tx_id, Some(&block_hash))
-                .unwrap();
-            prettify_data(data);
-        } else if function_name == "get_raw_transaction_hex" {
-            let tx_id = take_input("transaction id ").parse().unwrap();
-            let height = get_block_number();
-            let block_hash = client.get_block_hash(height).unwrap();
-            let data = client
-                .get_raw_transaction_hex(&tx_id, Some(&block_hash))
-                .unwrap();
-            println!("{}", data);
-        } else if function_name == "get_raw_transaction_info" {
-            let tx_id = take_input("transaction id ").parse().unwrap();
-            let height = get_block_number();
-            let block_hash = client.get_block_hash(height).unwrap();
-            let data = client
-                .get_raw_transaction_info(&tx_id, Some(&block_hash))
-                .unwrap();
-            prettify_data(data);
-        } else if function_name == "get_block_filter" {
-            let height = get_block_number();
-            let block_hash = client.get_block_hash(height).unwrap();
-            let data = client.get_block_filter(&block_hash).unwrap();
-            prettify_data(data);
-        } else if function_name == "get_balance" {
-            let min_conf = take_input("minimum confirmation").parse().unwrap();
-            let include_watchonly = get_bool("include_watchonly");
-            let data = client
-                .get_balance(Some(min_conf), Some(include_watchonly))
-                .unwrap();
-            prettify_data(data);
-        } else if function_name == "get_transaction" {
-            let tx_id = take_input("transaction id ").parse().unwrap();
-            let include_watchonly = get_bool("include_watchonly");
-            let data = client
-                .get_transaction(&tx_id, Some(include_watchonly))
-                .unwrap();
-            prettify_data(data);
-        } else if function_name == "list_transactions" {
-            let label = take_input("label");
-            let count = take_input("count").parse().unwrap();
-            let skip = take_input("skip").parse().unwrap();
-            let include_watchonly = get_bool("include_watchonly");
-
-            let data = client
-                .list_transactions(
-                    Some(label.trim()),
-                    Some(count),
-                    Some(skip),
-                    Some(include_watchonly),
-                )
-                .unwrap();
-            print_object(data);
-        } else if function_name == "list_since_block" {
-            let height = get_block_number();
-            let block_hash = client.get_block_hash(height).unwrap();
-            let target_confirmations = take_input("count").parse().unwrap();
-            let include_watchonly = get_bool("include_watchonly");
-            let include_removed = get_bool("include_removed");
-            let data = client
-                .list_since_block(
-                    Some(&block_hash),
-                    Some(target_confirmations),
-                    Some(include_watchonly),
-                    Some(include_removed),
-                )
-                .unwrap();
-            prettify_data(data);
-        } else if function_name == "get_tx_out_proof" {
-            let tx_id = get_txid_array("transaction id array");
-            let height = get_block_number();
-            let block_hash = client.get_block_hash(height).unwrap();
-            let data = client.get_tx_out_proof(&tx_id, Some(&block_hash)).unwrap();
-            print_object(data);
-        // } else if function_name == "import_public_key" {
-        //     client.import_public_key().unwrap();
-        // } else if function_name == "import_private_key" {
-        //     client.import_private_key().unwrap();
-        // } else if function_name == "import_address" {
-        //     client.import_address().unwrap();
-        // } else if function_name == "import_address_script" {
-        //     client.import_address_script().unwrap();
-        // } else if function_name == "import_multi" {
-        //     let data = client.import_multi().unwrap();
-        //     print_object(data);
-        // } else if function_name == "import_descriptors" {
-        //     let data = client.import_descriptors().unwrap();
-        //     print_object(data);
-        // } else if function_name == "list_unspent" {
-        //     let data = client.list_unspent().unwrap();
-        //     print_object(data);
-        } else if function_name == "list_received_by_address" {
-            let address_filter = get_address();
-            let min_conf = take_input("minimum confirmation").parse().unwrap();
-            let include_empty = get_bool("include empty");
-            let include_watchonly = get_bool("include watchonly");
-
-            let data = client
-                .list_received_by_address(
-                    Some(&address_filter),
-                    Some(min_conf),
-                    Some(include_empty),
-                    Some(include_watchonly),
-                )
-                .unwrap();
-            print_object(data);
-        // } else if function_name == "create_psbt" {
-        //     let data = client.create_psbt().unwrap();
-        //     println!("{}", data);
-        // } else if function_name == "create_raw_transaction_hex" {
-        //     let data = client.create_raw_transaction_hex().unwrap();
-        //     println!("{}", data);
-        // } else if function_name == "create_raw_transaction" {
-        //     let data = client.create_raw_transaction().unwrap();
-        //     prettify_data(data);
-        } else if function_name == "decode_raw_transaction" {
-            let tx = take_input("enter transaction");
-            let is_witness = get_bool("is witness");
-
-            let data = client
-                .decode_raw_transaction(tx.trim(), Some(is_witness))
-                .unwrap();
-            prettify_data(data);
-        // } else if function_name == "fund_raw_transaction" {
-        //     let data = client.fund_raw_transaction().unwrap();
-        //     prettify_data(data);
-        // } else if function_name == "sign_raw_transaction" {
-        //     let data = client.sign_raw_transaction().unwrap();
-        //     prettify_data(data);
-        // } else if function_name == "sign_raw_transaction_with_wallet" {
-        //     let data = client.sign_raw_transaction_with_wallet().unwrap();
-        //     prettify_data(data);
-        // } else if function_name == "sign_raw_transaction_with_key" {
-        //     let data = client.sign_raw_transaction_with_key().unwrap();
-        //     prettify_data(data);
-        } else if function_name == "verify_message" {
-            let address = get_address();
-            let signature = take_input("signature").parse().unwrap();
-            let message = take_input("message");
-
-            let data = client
-                .verify_message(&address, &signature, message.trim())
-                .unwrap();
-            println!("{}", data);
-        } else if function_name == "get_new_address" {
-            let label = take_input("label");
-            let data = client.get_new_address(Some(label.trim()), None).unwrap();
-            prettify_data(data);
-        } else if function_name == "generate_to_address" {
-            let height = get_block_number();
-            let address = get_address();
-            let data = client.generate_to_address(height, &address).unwrap();
-            print_object(data);
-        // } else if function_name == "get_raw_mempool_verbose" {
-        //     let data = client.get_raw_mempool_verbose().unwrap();
-        //     print_hashmap(data);
-        // } else if function_name == "send_to_address" {
-        //     let data = client.send_to_address().unwrap();
-        //     prettify_data(data);
-        } else if function_name == "get_node_addresses" {
-            let count = take_input("count").trim().parse().unwrap();
-            let data = client.get_node_addresses(Some(count)).unwrap();
-            print_object(data);
-        // } else if function_name == "estimate_smart_fee" {
-        //     let data = client.estimate_smart_fee().unwrap();
-        //     prettify_data(data);
-        // } else if function_name == "wallet_create_funded_psbt" {
-        //     let data = client.wallet_create_funded_psbt().unwrap();
-        //     prettify_data(data);
-        // } else if function_name == "wallet_process_psbt" {
-        //     let data = client.wallet_process_psbt().unwrap();
-        //     prettify_data(data);
-        } else if function_name == "rescan_blockchain" {
-            let start_from = take_input("start from").trim().parse().unwrap();
-            let stop_height = take_input("stop from").trim().parse().unwrap();
-            let data = client
-                .rescan_blockchain(Some(start_from), Some(stop_height))
-                .unwrap();
-            println!("{:?}", data);
-            // } else if function_name == "get_tx_out_set_info" {
-            //     let data = client.get_tx_out_set_info().unwrap();
-            //     prettify_data(data);
-            // } else if function_name == "scan_tx_out_set_blocking" {
-            //     let data = client.scan_tx_out_set_blocking().unwrap();
-            //     prettify_data(data);
+fn dispatch(client: &mut Clients, function_name: &str) -> Result<(), Box<dyn Error>> {
+    if function_name == "get_block_count" {
+        let data = client.get_block_count()?;
+        println!("Current Block Count : {}", data);
+    } else if function_name == "get_latest_block_hash" {
+        let data = client.get_latest_block_hash()?;
+        println!("Current Block Hash : {}", data);
+    } else if function_name == "get_block" {
+        let height = get_block_number()?;
+        let block_hash = client.get_block_hash(height)?;
+        let block = client.get_block(block_hash)?;
+        prettify_data(block);
+    } else if function_name == "get_best_block" {
+        let block = client.get_best_block()?;
+        prettify_data(block);
+    } else if function_name == "get_network_info" {
+        let data = client.get_network_info()?;
+        prettify_data(data);
+    } else if function_name == "get_index_info" {
+        let data = client.get_index_info()?;
+        prettify_data(data);
+    } else if function_name == "version" {
+        let data = client.version()?;
+        prettify_data(data);
+    } else if function_name == "load_wallet" {
+        let wallet = take_input("wallet");
+        let data = client.load_wallet(&wallet)?;
+        prettify_data(data);
+    } else if function_name == "unload_wallet" {
+        let wallet = take_input("wallet");
+        let data = client.unload_wallet(Some(&wallet))?;
+        if client.active_wallet.as_deref() == Some(wallet.as_str()) {
+            client.use_wallet(None)?;
         }
+        prettify_data(data);
+    } else if function_name == "use_wallet" {
+        let wallet = take_input("wallet (- for the node endpoint)");
+        if wallet.is_empty() || wallet == "-" {
+            client.use_wallet(None)?;
+            println!("Using the node endpoint");
+        } else {
+            client.use_wallet(Some(&wallet))?;
+            println!("Using wallet {}", wallet::display_name(&wallet));
+        }
+    } else if function_name == "wallet_overview" {
+        let data = client.wallet_overview()?;
+        prettify_data(data);
+    } else if function_name == "list_wallets" {
+        let data = client.list_wallets()?;
+        for wallet in data.iter() {
+            println!("{}", wallet);
+        }
+    } else if function_name == "list_wallet_dir" {
+        let data = client.list_wallet_dir()?;
+        for wallet_dir in data.iter() {
+            println!("{}", wallet_dir);
+        }
+    } else if function_name == "get_wallet_info" {
+        let data = client.get_wallet_info()?;
+        prettify_data(data);
+    } else if function_name == "backup_wallet" {
+        let destination = take_input("destination path");
+        client.backup_wallet(&destination)?;
+        println!("Wallet backed up to {}", destination);
+    } else if function_name == "schedule_backups" {
+        let dir = take_input("backup directory");
+        let interval: u64 = take_input("interval in seconds").parse()?;
+        let keep: usize = take_input("backups to keep per wallet").parse()?;
+        let rounds: usize = take_input("rounds (0 to run until stop_backups)").parse()?;
+
+        let count = client.schedule_backups(&dir, Duration::from_secs(interval), keep, rounds)?;
+        println!(
+            "Backing up {} wallets, check them with backup_status or stop with stop_backups",
+            count
+        );
+    } else if function_name == "backup_status" {
+        let backups = client.backups.as_ref().ok_or("no backups were scheduled")?;
+        for line in backups.take_log() {
+            println!("{}", line);
+        }
+        if backups.is_finished() {
+            println!("Scheduled backups finished");
+        }
+    } else if function_name == "stop_backups" {
+        let backups = client.backups.take().ok_or("no backups were scheduled")?;
+        for line in backups.stop()? {
+            println!("{}", line);
+        }
+        println!("Scheduled backups stopped");
+    } else if function_name == "dump_private_key" {
+        let address = get_address()?;
+        let p_key = client.dump_private_key(address)?;
+        println!("{}", p_key);
+    } else if function_name == "encrypt_wallet" {
+        let address = take_input("passphrase");
+        client.encrypt_wallet(&address)?;
+    } else if function_name == "get_difficulty" {
+        let data = client.get_difficulty()?;
+        println!("{}", data);
+    } else if function_name == "get_connection_count" {
+        let data = client.get_connection_count()?;
+        println!("{}", data);
+    } else if function_name == "get_block_hex" {
+        let height = get_block_number()?;
+        let block_hash = client.get_block_hash(height)?;
+        let data = client.get_block_hex(block_hash)?;
+        println!("{}", data);
+    } else if function_name == "get_block_info" {
+        let height = get_block_number()?;
+        let block_hash = client.get_block_hash(height)?;
+        let block = client.get_block_info(block_hash)?;
+        prettify_data(block);
+    } else if function_name == "get_block_header" {
+        let height = get_block_number()?;
+        let block_hash = client.get_block_hash(height)?;
+        let block = client.get_block_header(block_hash)?;
+        prettify_data(block);
+    } else if function_name == "get_mining_info" {
+        let data = client.get_mining_info()?;
+        prettify_data(data);
+    } else if function_name == "get_blockchain_info" {
+        let data = client.get_blockchain_info()?;
+        prettify_data(data);
+    } else if function_name == "get_best_block_hash" {
+        let data = client.get_best_block_hash()?;
+        println!("{}", data);
+    } else if function_name == "get_block_hash" {
+        let height = get_block_number()?;
+        let data = client.get_block_hash(height)?;
+        println!("{}", data);
+    } else if function_name == "get_block_stats" {
+        let height = get_block_number()?;
+        let data = client.get_block_stats(height)?;
+        prettify_data(data);
+    } else if function_name == "get_balances" {
+        let data = client.get_balances()?;
+        prettify_data(data);
+    } else if function_name == "get_received_by_address" {
+        let address = get_address()?;
+        let min_conf = take_input("minimum confirmation").parse()?;
+
+        let data = client.get_received_by_address(&address, Some(min_conf))?;
+        println!("{}", data);
+    } else if function_name == "set_label" {
+        let address = get_address()?;
+        let label = take_input("label");
+
+        client.set_label(&address, label.trim())?;
+    } else if function_name == "key_pool_refill" {
+        let size = take_input("new_size").parse()?;
+        client.key_pool_refill(Some(size))?;
+    // } else if function_name == "lock_unspent" {
+    //     let data = client.lock_unspent()?;
+    //     println!("{}", data);
+    // } else if function_name == "unlock_unspent" {
+    //     let data = client.unlock_unspent()?;
+    //     println!("{}", data);
+    } else if function_name == "unlock_unspent_all" {
+        let data = client.unlock_unspent_all()?;
+        println!("{}", data);
+    } else if function_name == "stop" {
+        client.stop()?;
+    // } else if function_name == "get_raw_change_address" {
+    //     let data = client.get_raw_change_address()?;
+    //     println!("{}", data);
+    } else if function_name == "get_address_info" {
+        let address = get_address()?;
+        let data = client.get_address_info(&address)?;
+        prettify_data(data);
+    } else if function_name == "generate" {
+        let block_number = take_input("block number").parse()?;
+        let max_tries = take_input("max tries").parse()?;
+        let data = client.generate(block_number, Some(max_tries))?;
+        print_object(data);
+    } else if function_name == "invalidate_block" {
+        let height = get_block_number()?;
+        let block_hash = client.get_block_hash(height)?;
+        client.invalidate_block(block_hash)?;
+    } else if function_name == "reconsider_block" {
+        let height = get_block_number()?;
+        let block_hash = client.get_block_hash(height)?;
+        client.reconsider_block(block_hash)?;
+    } else if function_name == "get_mempool_entry" {
+        let tx_id = take_input("transaction id ").parse()?;
+        let data = client.get_mempool_entry(tx_id)?;
+        prettify_data(data);
+    } else if function_name == "get_raw_mempool" {
+        let data = client.get_raw_mempool()?;
+        print_object(data);
+    } else if function_name == "get_chain_tips" {
+        let data = client.get_chain_tips()?;
+        print_object(data);
+    } else if function_name == "add_node" {
+        let node_address = get_node_address();
+
+        client.add_node(&node_address)?;
+    } else if function_name == "remove_node" {
+        let node_address = get_node_address();
+        client.remove_node(&node_address)?;
+    } else if function_name == "onetry_node" {
+        let node_address = get_node_address();
+        client.onetry_node(&node_address)?;
+    } else if function_name == "disconnect_node" {
+        let node_address = get_node_address();
+        client.disconnect_node(&node_address)?;
+    } else if function_name == "disconnect_node_by_id" {
+        let node_id = take_input("node id ").parse()?;
+
+        client.disconnect_node_by_id(node_id)?;
+    } else if function_name == "get_added_node_info" {
+        let node = take_input("node ");
+        let data = client.get_added_node_info(Some(&node))?;
+        print_object(data);
+    } else if function_name == "list_banned" {
+        let data = client.list_banned()?;
+        print_object(data);
+    } else if function_name == "clear_banned" {
+        client.clear_banned()?;
+    // } else if function_name == "add_ban" {
+    //     // subnet: &str, bantime: u64, absolute: bool
+    //     client.add_ban()?;
+    // } else if function_name == "remove_ban" {
+    //     client.remove_ban()?;
+    } else if function_name == "set_network_active" {
+        let input = get_bool("\n0. for false \n1. for true")?;
+        let data = client.set_network_active(input)?;
+        println!("{}", data);
+    } else if function_name == "get_peer_info" {
+        let data = client.get_peer_info()?;
+        print_object(data);
+    } else if function_name == "ping" {
+        client.ping()?;
+    // } else if function_name == "send_raw_transaction" {
+    //     let data = client.send_raw_transaction()?;
+    //     println!("{}", data);
+    } else if function_name == "wait_for_new_block" {
+        let height = get_block_number()?;
+        let block_hash = client.get_block_hash(height)?;
+        let timeout = take_input("time out").parse()?;
+        let data = client.wait_for_new_block(block_hash, timeout)?;
+        prettify_data(data);
+    } else if function_name == "get_descriptor_info" {
+        let input = take_input("desc");
+        let data = client.get_descriptor_info(&input)?;
+        prettify_data(data);
+    } else if function_name == "join_psbt" {
+        let data = get_string_array("psbt array")?;
+        let data = client.join_psbt(&data)?;
+        println!("{}", data);
+    } else if function_name == "combine_psbt" {
+        let data = get_string_array("psbt array")?;
+        let data = client.combine_psbt(&data)?;
+        println!("{}", data);
+    } else if function_name == "combine_raw_transaction" {
+        let data = get_string_array("raw transaction array")?;
+        let data = client.combine_raw_transaction(&data)?;
+        println!("{}", data);
+    } else if function_name == "finalize_psbt" {
+        let psbt = take_input("psbt");
+        let input = get_bool("do you want to extract?")?;
+        let data = client.finalize_psbt(&psbt, Some(input))?;
+        prettify_data(data);
+    // } else if function_name == "derive_addresses" {
+    //     let data = client.derive_addresses()?;
+    //     print_object(data);
+    } else if function_name == "get_net_totals" {
+        let data = client.get_net_totals()?;
+        prettify_data(data);
+    } else if function_name == "get_network_hash_ps" {
+        let nblocks = take_input("number of blocks").parse()?;
+        let height = take_input("height").parse()?;
+        let data = client.get_network_hash_ps(Some(nblocks), Some(height))?;
+        println!("{}", data);
+    } else if function_name == "uptime" {
+        let data = client.uptime()?;
+        println!("{}", data);
+    // } else if function_name == "submit_block" {
+    //     client.submit_block()?;
+    // } else if function_name == "submit_block_bytes" {
+    //     client.submit_block_bytes()?;
+    } else if function_name == "submit_block_hex" {
+        let nblocks = take_input("block hex");
+
+        client.submit_block_hex(nblocks.trim())?;
+    // } else if function_name == "add_multisig_address" {
+    //     let data = client.add_multisig_address()?;
+    //     prettify_data(data);
+    } else if function_name == "create_wallet" {
+        let wallet = take_input("wallet");
+        let descriptors = get_bool("descriptor wallet")?;
+        let disable_private_keys = get_bool("disable private keys")?;
+        let blank = get_bool("blank")?;
+        let passphrase = take_input("passphrase (empty for none)");
+        let avoid_reuse = get_bool("avoid reuse")?;
+
+        let passphrase = Some(passphrase.as_str()).filter(|passphrase| !passphrase.is_empty());
+        let data = client.create_wallet(
+            &wallet,
+            disable_private_keys,
+            blank,
+            passphrase,
+            avoid_reuse,
+            descriptors,
+        )?;
+        prettify_data(data);
+    } else if function_name == "get_block_header_info" {
+        let height = get_block_number()?;
+        let block_hash = client.get_block_hash(height)?;
+        let data = client.get_block_header_info(&block_hash)?;
+        prettify_data(data);
+    } else if function_name == "get_block_template" {
+        let template = client.get_segwit_block_template()?;
+        prettify_data(TemplateSummary::new(&template));
+    } else if function_name == "mine" {
+        let template = client.get_segwit_block_template()?;
+        prettify_data(TemplateSummary::new(&template));
+
+        let payout = parse_address(&take_input("payout address"))?.script_pubkey();
+        let coinbase_message = take_input("coinbase message");
+        let mut block = miner::assemble_block(&template, payout, &coinbase_message)?;
+
+        println!("Grinding nonce for block {}", template.height);
+        let block_hash = miner::grind_nonce(&mut block)?;
+        println!("Block weight : {}", block.weight());
+
+        match client.submit_mined_block(&block)? {
+            None => println!("Submitted block {}", block_hash),
+            Some(reason) => println!("Block {} rejected : {}", block_hash, reason),
+        }
+    // } else if function_name == "get_block_stats_fields" {
+    //     let data = client.get_block_stats_fields()?;
+    //     prettify_data(data);
+    } else if function_name == "get_raw_transaction" {
+        let tx_id = take_input("transaction id ").parse()?;
+        let height = get_block_number()?;
+        let block_hash = client.get_block_hash(height)?;
+        let data = client.get_raw_transaction(&tx_id, Some(&block_hash))?;
+        prettify_data(data);
+    } else if function_name == "get_raw_transaction_hex" {
+        let tx_id = take_input("transaction id ").parse()?;
+        let height = get_block_number()?;
+        let block_hash = client.get_block_hash(height)?;
+        let data = client.get_raw_transaction_hex(&tx_id, Some(&block_hash))?;
+        println!("{}", data);
+    } else if function_name == "get_raw_transaction_info" {
+        let tx_id = take_input("transaction id ").parse()?;
+        let height = get_block_number()?;
+        let block_hash = client.get_block_hash(height)?;
+        let data = client.get_raw_transaction_info(&tx_id, Some(&block_hash))?;
+        prettify_data(data);
+    } else if function_name == "get_block_filter" {
+        let height = get_block_number()?;
+        let block_hash = client.get_block_hash(height)?;
+        let data = client.get_block_filter(&block_hash)?;
+        prettify_data(data);
+    } else if function_name == "get_balance" {
+        let min_conf = take_input("minimum confirmation").parse()?;
+        let include_watchonly = get_bool("include_watchonly")?;
+        let data = client.get_balance(Some(min_conf), Some(include_watchonly))?;
+        prettify_data(data);
+    } else if function_name == "get_transaction" {
+        let tx_id = take_input("transaction id ").parse()?;
+        let include_watchonly = get_bool("include_watchonly")?;
+        let data = client.get_transaction(&tx_id, Some(include_watchonly))?;
+        prettify_data(data);
+    } else if function_name == "list_transactions" {
+        let label = take_input("label");
+        let count = take_input("count").parse()?;
+        let skip = take_input("skip").parse()?;
+        let include_watchonly = get_bool("include_watchonly")?;
+
+        let data = client.list_transactions(
+            Some(label.trim()),
+            Some(count),
+            Some(skip),
+            Some(include_watchonly),
+        )?;
+        print_object(data);
+    } else if function_name == "export_history" {
+        let csv_path = take_input("csv path");
+        let cursor_path = take_input("cursor path");
+        let min_confirmations = take_input("minimum confirmations").parse()?;
+        let include_watchonly = get_bool("include_watchonly")?;
+
+        let exporter = HistoryExporter::new(&client.rpc, min_confirmations, include_watchonly);
+        let data = exporter.export(&csv_path, &cursor_path)?;
+        prettify_data(data);
+    } else if function_name == "list_since_block" {
+        let height = get_block_number()?;
+        let block_hash = client.get_block_hash(height)?;
+        let target_confirmations = take_input("count").parse()?;
+        let include_watchonly = get_bool("include_watchonly")?;
+        let include_removed = get_bool("include_removed")?;
+        let data = client.list_since_block(
+            Some(&block_hash),
+            Some(target_confirmations),
+            Some(include_watchonly),
+            Some(include_removed),
+        )?;
+        prettify_data(data);
+    } else if function_name == "get_tx_out_proof" {
+        let tx_id = get_txid_array("transaction id array")?;
+        let height = get_block_number()?;
+        let block_hash = client.get_block_hash(height)?;
+        let data = client.get_tx_out_proof(&tx_id, Some(&block_hash))?;
+        print_object(data);
+    // } else if function_name == "import_public_key" {
+    //     client.import_public_key()?;
+    // } else if function_name == "import_private_key" {
+    //     client.import_private_key()?;
+    // } else if function_name == "import_address" {
+    //     client.import_address()?;
+    // } else if function_name == "import_address_script" {
+    //     client.import_address_script()?;
+    // } else if function_name == "import_multi" {
+    //     let data = client.import_multi()?;
+    //     print_object(data);
+    // } else if function_name == "import_descriptors" {
+    //     let data = client.import_descriptors()?;
+    //     print_object(data);
+    // } else if function_name == "list_unspent" {
+    //     let data = client.list_unspent()?;
+    //     print_object(data);
+    } else if function_name == "list_received_by_address" {
+        let address_filter = get_address()?;
+        let min_conf = take_input("minimum confirmation").parse()?;
+        let include_empty = get_bool("include empty")?;
+        let include_watchonly = get_bool("include watchonly")?;
+
+        let data = client.list_received_by_address(
+            Some(&address_filter),
+            Some(min_conf),
+            Some(include_empty),
+            Some(include_watchonly),
+        )?;
+        print_object(data);
+    // } else if function_name == "create_psbt" {
+    //     let data = client.create_psbt()?;
+    //     println!("{}", data);
+    // } else if function_name == "create_raw_transaction_hex" {
+    //     let data = client.create_raw_transaction_hex()?;
+    //     println!("{}", data);
+    // } else if function_name == "create_raw_transaction" {
+    //     let data = client.create_raw_transaction()?;
+    //     prettify_data(data);
+    } else if function_name == "decode_raw_transaction" {
+        let tx = take_input("enter transaction");
+        let is_witness = get_bool("is witness")?;
+
+        let data = client.decode_raw_transaction(tx.trim(), Some(is_witness))?;
+        prettify_data(data);
+    // } else if function_name == "fund_raw_transaction" {
+    //     let data = client.fund_raw_transaction()?;
+    //     prettify_data(data);
+    // } else if function_name == "sign_raw_transaction" {
+    //     let data = client.sign_raw_transaction()?;
+    //     prettify_data(data);
+    // } else if function_name == "sign_raw_transaction_with_wallet" {
+    //     let data = client.sign_raw_transaction_with_wallet()?;
+    //     prettify_data(data);
+    // } else if function_name == "sign_raw_transaction_with_key" {
+    //     let data = client.sign_raw_transaction_with_key()?;
+    //     prettify_data(data);
+    } else if function_name == "sign" {
+        let address = parse_address(&take_input("address"))?;
+        let message = take_input("message");
+        let key = take_input("private key WIF (wallet to use the node wallet)");
+
+        let signature = if key.is_empty() || key == "wallet" {
+            client.sign_message(&address, &message)?
+        } else {
+            let key = PrivateKey::from_wif(&key)?;
+            message::sign(&key, &address, &message)?
+        };
+        print_plain(signature);
+    } else if function_name == "verify" {
+        let address = parse_address(&take_input("address"))?;
+        let signature = take_input("signature");
+        let message = take_input("message");
+
+        let data = message::verify(&address, &signature, &message)?;
+        println!("{}", data);
+    } else if function_name == "get_new_address" {
+        let label = take_input("label");
+        let data = client.get_new_address(Some(label.trim()), None)?;
+        prettify_data(data);
+    } else if function_name == "generate_to_address" {
+        let height = get_block_number()?;
+        let address = get_address()?;
+        let data = client.generate_to_address(height, &address)?;
+        print_object(data);
+    // } else if function_name == "get_raw_mempool_verbose" {
+    //     let data = client.get_raw_mempool_verbose()?;
+    //     print_hashmap(data);
+    // } else if function_name == "send_to_address" {
+    //     let data = client.send_to_address()?;
+    //     prettify_data(data);
+    } else if function_name == "get_node_addresses" {
+        let count = take_input("count").trim().parse()?;
+        let data = client.get_node_addresses(Some(count))?;
+        print_object(data);
+    // } else if function_name == "estimate_smart_fee" {
+    //     let data = client.estimate_smart_fee()?;
+    //     prettify_data(data);
+    // } else if function_name == "wallet_create_funded_psbt" {
+    //     let data = client.wallet_create_funded_psbt()?;
+    //     prettify_data(data);
+    // } else if function_name == "wallet_process_psbt" {
+    //     let data = client.wallet_process_psbt()?;
+    //     prettify_data(data);
+    } else if function_name == "rescan_blockchain" {
+        let start_from = take_input("start from").trim().parse()?;
+        let stop_height = take_input("stop from").trim().parse()?;
+        let data = client.rescan_blockchain(Some(start_from), Some(stop_height))?;
+        println!("{:?}", data);
+    } else if function_name == "scan_block_filters" {
+        let start = take_input("start height").trim().parse()?;
+        let stop = take_input("stop height").trim().parse()?;
+        let scripts = get_script_array("number of addresses or scripts")?;
+        let data = client.scan_block_filters(start, stop, scripts)?;
+        print_object(data);
+    } else if function_name == "read_block_files" {
+        let reader = get_block_file_reader()?;
+        let limit = get_block_limit()?;
+        reader.for_each_block(limit, |_, block| prettify_data(block))?;
+    } else if function_name == "block_file_stats" {
+        let reader = get_block_file_reader()?;
+        let limit = get_block_limit()?;
+        let data = reader.block_stats(limit)?;
+        print_object(data);
+    } else if function_name == "get_tx_out_set_info" {
+        let data = client.get_tx_out_set_info(None, None, None)?;
+        prettify_data(data);
+    } else if function_name == "scan_tx_out_set_blocking" {
+        let requests: Vec<ScanTxOutRequest> = get_string_array("descriptor array")?
+            .iter()
+            .map(|input| utxo::scan_request(input))
+            .collect();
+        let data = client.scan_tx_out_set_blocking(&requests)?;
+        prettify_data(data);
+    } else if function_name == "utxo_set_info" {
+        let hash_type = match take_input("hash type (muhash or none)").as_str() {
+            "muhash" => TxOutSetHashType::Muhash,
+            _ => TxOutSetHashType::None,
+        };
+        let data = client.get_tx_out_set_info(Some(hash_type), None, None)?;
+        prettify_data(UtxoSetSummary::new(data));
+    } else if function_name == "scan_utxos" {
+        let requests = get_string_array("descriptor or address array")?
+            .iter()
+            .map(|input| utxo::scan_request(input))
+            .collect();
+        client.start_utxo_scan(requests)?;
+        println!("Scan started, check it with scan_status or stop it with abort_scan");
+    } else if function_name == "scan_status" {
+        if let Some(report) = client.poll_utxo_scan()? {
+            prettify_data(report);
+        }
+    } else if function_name == "abort_scan" {
+        let aborted = utxo::abort_scan(&client.rpc)?;
+        println!("Scan aborted : {}", aborted);
+    } else {
+        return Err(format!("{function_name} is not implemented").into());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::utils::get_last_result;
+    use std::fs;
+
+    const TXID: &str = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b";
+
+    /// Client replaying `lines`, `test` keeps the file apart from parallel tests.
+    fn replay_client(test: &str, lines: &[String]) -> Clients<'static> {
+        let name = format!("rs-btc-replay-{}-{test}.jsonl", std::process::id());
+        let path = std::env::temp_dir().join(name);
+        fs::write(&path, lines.join("\n")).unwrap();
+
+        // The session reads every call up front, the file is not needed afterwards.
+        let args = ["--replay".to_string(), path.display().to_string()];
+        let session = Session::from_args(args.into_iter());
+        fs::remove_file(&path).unwrap();
+
+        Clients::new(
+            "http://localhost:8332",
+            "abc".to_string(),
+            "abc".to_string(),
+            session.unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn replays_recorded_calls_through_dispatch() {
+        let line = format!(
+            r#"{{"url":"http://localhost:8332","method":"getrawmempool","params":[],"result":["{TXID}"],"error":null,"transport_error":null}}"#
+        );
+        let mut client = replay_client("dispatch", &[line]);
+
+        dispatch(&mut client, "get_raw_mempool").unwrap();
+        assert_eq!(get_last_result(), Some(serde_json::json!([TXID])));
+
+        // Every recorded call answers once.
+        assert!(dispatch(&mut client, "get_raw_mempool").is_err());
+    }
+
+    #[test]
+    fn redacted_calls_fail_on_replay() {
+        let line = r#"{"url":"http://localhost:8332","method":"dumpprivkey","params":null,"result":null,"error":null,"transport_error":null,"redacted":true}"#;
+        let mut client = replay_client("redacted", &[line.to_string()]);
+
+        set_pending_input(vec!["0000000000000000000000000000000000000000".to_string()]);
+        let err = dispatch(&mut client, "dump_private_key").unwrap_err();
+        assert!(err.to_string().contains("without its secrets"));
     }
 }
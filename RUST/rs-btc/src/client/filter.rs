@@ -0,0 +1,181 @@
+use bitcoincore_rpc::{
+    bitcoin::{Block, BlockHash, OutPoint, ScriptBuf, Txid},
+    RpcApi,
+};
+use serde::Serialize;
+use std::{collections::HashSet, error::Error};
+
+/// How often (in blocks) the scanner reports its progress.
+const PROGRESS_INTERVAL: u64 = 1000;
+
+#[derive(Debug, Serialize)]
+pub struct FilterMatch {
+    pub height: u64,
+    pub block_hash: BlockHash,
+    pub txid: Txid,
+    pub received: Vec<u32>,
+    pub spent: Vec<OutPoint>,
+}
+
+/// Light-weight rescan using BIP158 basic block filters.
+///
+/// Filters are matched client-side and only blocks with a filter hit are
+/// downloaded. Spends are detected for outputs found earlier in the same
+/// scan, so the start height should be at or before the wallet birthday.
+pub struct FilterScanner<'a, R: RpcApi> {
+    rpc: &'a R,
+    scripts: Vec<ScriptBuf>,
+    watched_outpoints: HashSet<OutPoint>,
+}
+
+impl<'a, R: RpcApi> FilterScanner<'a, R> {
+    pub fn new(rpc: &'a R, scripts: Vec<ScriptBuf>) -> FilterScanner<'a, R> {
+        FilterScanner {
+            rpc,
+            scripts,
+            watched_outpoints: HashSet::new(),
+        }
+    }
+
+    // `u64::is_multiple_of` needs Rust 1.87.
+    #[allow(clippy::manual_is_multiple_of)]
+    pub fn scan(&mut self, start: u64, stop: u64) -> Result<Vec<FilterMatch>, Box<dyn Error>> {
+        let mut matches = Vec::new();
+
+        if self.scripts.is_empty() {
+            return Ok(matches);
+        }
+
+        for height in start..=stop {
+            if (height - start) % PROGRESS_INTERVAL == 0 {
+                println!("Scanning filters {height}/{stop}");
+            }
+
+            let block_hash = self.rpc.get_block_hash(height)?;
+            let filter = self.rpc.get_block_filter(&block_hash)?.into_filter();
+            let query = self.scripts.iter().map(|script| script.as_bytes());
+
+            if !filter.match_any(&block_hash, query)? {
+                continue;
+            }
+
+            let block = self.rpc.get_block(&block_hash)?;
+            matches.extend(self.match_block(height, block_hash, &block));
+        }
+
+        println!("Found {} matching transactions", matches.len());
+        Ok(matches)
+    }
+
+    fn match_block(
+        &mut self,
+        height: u64,
+        block_hash: BlockHash,
+        block: &Block,
+    ) -> Vec<FilterMatch> {
+        let mut matches = Vec::new();
+
+        for tx in block.txdata.iter() {
+            let txid = tx.compute_txid();
+
+            let spent: Vec<OutPoint> = tx
+                .input
+                .iter()
+                .filter(|input| self.watched_outpoints.remove(&input.previous_output))
+                .map(|input| input.previous_output)
+                .collect();
+
+            let received: Vec<u32> = tx
+                .output
+                .iter()
+                .enumerate()
+                .filter(|(_, output)| self.scripts.contains(&output.script_pubkey))
+                .map(|(vout, _)| vout as u32)
+                .collect();
+
+            for vout in received.iter() {
+                self.watched_outpoints.insert(OutPoint::new(txid, *vout));
+            }
+
+            if !received.is_empty() || !spent.is_empty() {
+                matches.push(FilterMatch {
+                    height,
+                    block_hash,
+                    txid,
+                    received,
+                    spent,
+                });
+            }
+        }
+
+        matches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoincore_rpc::bitcoin::{
+        bip158::BlockFilter, blockdata::constants::genesis_block, hex::FromHex, transaction,
+        Network, Transaction, TxIn,
+    };
+
+    /// Answers no calls, `match_block` works on the block it is given.
+    struct NoRpc;
+
+    impl RpcApi for NoRpc {
+        fn call<T: for<'a> serde::de::Deserialize<'a>>(
+            &self,
+            cmd: &str,
+            _: &[serde_json::Value],
+        ) -> bitcoincore_rpc::Result<T> {
+            unreachable!("unexpected {cmd} call")
+        }
+    }
+
+    #[test]
+    fn matches_the_bip158_testnet_genesis() {
+        // Block 0 of the BIP158 test vectors, the testnet genesis.
+        let block = genesis_block(Network::Testnet);
+        let block_hash = block.block_hash();
+        assert_eq!(
+            block_hash.to_string(),
+            "000000000933ea01ad0ee984209779baaec3ced90fa3f408719526f8d77f4943"
+        );
+        let filter = BlockFilter::new(&Vec::from_hex("019dfca8").unwrap());
+        let coinbase = &block.txdata[0];
+        let script = coinbase.output[0].script_pubkey.clone();
+
+        assert!(filter
+            .match_any(&block_hash, [script.as_bytes()].into_iter())
+            .unwrap());
+        let other = ScriptBuf::from_hex("0014751e76e8199196d454941c45d1b3a323f1433bd6").unwrap();
+        assert!(!filter
+            .match_any(&block_hash, [other.as_bytes()].into_iter())
+            .unwrap());
+
+        let mut scanner = FilterScanner::new(&NoRpc, vec![script]);
+        let matches = scanner.match_block(0, block_hash, &block);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].txid, coinbase.compute_txid());
+        assert_eq!(matches[0].received, [0]);
+        assert!(matches[0].spent.is_empty());
+
+        // A later spend of the found output is reported once.
+        let outpoint = OutPoint::new(coinbase.compute_txid(), 0);
+        let mut spending = block.clone();
+        spending.txdata = vec![Transaction {
+            version: transaction::Version::TWO,
+            lock_time: coinbase.lock_time,
+            input: vec![TxIn {
+                previous_output: outpoint,
+                ..Default::default()
+            }],
+            output: Vec::new(),
+        }];
+        let matches = scanner.match_block(1, block_hash, &spending);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].spent, [outpoint]);
+        assert!(scanner.match_block(2, block_hash, &spending).is_empty());
+    }
+}
@@ -0,0 +1,405 @@
+use bitcoincore_rpc::{
+    bitcoin::{BlockHash, SignedAmount, Txid},
+    json::{GetTransactionResultDetailCategory, ListTransactionResult},
+    RpcApi,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashSet,
+    error::Error,
+    fs::{self, OpenOptions},
+    io::Write,
+};
+
+const PAGE_SIZE: usize = 500;
+const CSV_HEADER: &str = "txid,vout,category,address,label,amount_btc,amount_sats,fee_btc,fee_sats,confirmations,block_height,block_hash,block_time,block_time_utc";
+
+/// Wallet entries, entries removed by a reorg and the cursor they run up to.
+type Entries = (
+    Vec<ListTransactionResult>,
+    Vec<ListTransactionResult>,
+    ExportCursor,
+);
+
+/// Where the previous export stopped, written next to the CSV after every run.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportCursor {
+    pub last_block: BlockHash,
+    pub height: u64,
+    /// Coinbase transactions held back until they mature.
+    #[serde(default)]
+    pub immature: Vec<Txid>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportSummary {
+    pub incremental: bool,
+    pub rows: usize,
+    pub removed: usize,
+    pub matured: usize,
+    pub cursor: ExportCursor,
+}
+
+/// Exports wallet history to CSV, only including transactions with at least
+/// `min_confirmations` so later exports never need to revise earlier rows.
+/// Immature coinbase outputs would later turn into `generate` (or `orphan`),
+/// so they are kept in the cursor and exported once they mature.
+pub struct HistoryExporter<'a, R: RpcApi> {
+    rpc: &'a R,
+    min_confirmations: usize,
+    include_watchonly: bool,
+}
+
+impl<'a, R: RpcApi> HistoryExporter<'a, R> {
+    pub fn new(rpc: &'a R, min_confirmations: usize, include_watchonly: bool) -> Self {
+        HistoryExporter {
+            rpc,
+            min_confirmations: min_confirmations.max(1),
+            include_watchonly,
+        }
+    }
+
+    /// Appends every transaction confirmed since the stored cursor, or the full
+    /// history when `cursor_path` does not exist yet, then moves the cursor.
+    pub fn export(
+        &self,
+        csv_path: &str,
+        cursor_path: &str,
+    ) -> Result<ExportSummary, Box<dyn Error>> {
+        let previous: Option<ExportCursor> = match fs::read_to_string(cursor_path) {
+            Ok(data) => Some(serde_json::from_str(&data)?),
+            Err(_) => None,
+        };
+
+        let (transactions, removed, mut cursor) = match &previous {
+            Some(previous) => self.since(previous)?,
+            None => self.full()?,
+        };
+
+        let held = previous
+            .as_ref()
+            .map(|previous| previous.immature.as_slice())
+            .unwrap_or_default();
+        let (matured, mut immature) = self.matured(held)?;
+
+        let confirmed = transactions.iter().filter(|tx| {
+            tx.info
+                .blockheight
+                .is_some_and(|height| height as u64 <= cursor.height)
+        });
+
+        let mut seen = HashSet::new();
+        let mut rows = Vec::new();
+        for tx in matured.iter().chain(confirmed) {
+            if tx.detail.category == GetTransactionResultDetailCategory::Immature {
+                if !immature.contains(&tx.info.txid) {
+                    immature.push(tx.info.txid);
+                }
+            } else if seen.insert((tx.info.txid, tx.detail.vout, category(tx))) {
+                rows.push(csv_row(tx));
+            }
+        }
+        cursor.immature = immature;
+
+        for tx in removed.iter() {
+            println!(
+                "Warning: {} was removed by a reorg, check earlier exports",
+                tx.info.txid
+            );
+        }
+
+        write_rows(csv_path, &rows)?;
+        fs::write(cursor_path, serde_json::to_string_pretty(&cursor)?)?;
+
+        Ok(ExportSummary {
+            incremental: previous.is_some(),
+            rows: rows.len(),
+            removed: removed.len(),
+            matured: matured.len(),
+            cursor,
+        })
+    }
+
+    /// Entries confirmed after `previous`, plus those a reorg took back.
+    fn since(&self, previous: &ExportCursor) -> Result<Entries, Box<dyn Error>> {
+        // With target_confirmations listsinceblock returns the block that many
+        // blocks deep, everything up to it is final enough to export.
+        let since = self.rpc.list_since_block(
+            Some(&previous.last_block),
+            Some(self.min_confirmations),
+            Some(self.include_watchonly),
+            Some(true),
+        )?;
+        let height = self.rpc.get_block_header_info(&since.lastblock)?.height as u64;
+
+        let cursor = ExportCursor {
+            last_block: since.lastblock,
+            height,
+            immature: Vec::new(),
+        };
+        Ok((since.transactions, since.removed, cursor))
+    }
+
+    fn full(&self) -> Result<Entries, Box<dyn Error>> {
+        let tip = self.rpc.get_block_count()?;
+        let height = (tip + 1).saturating_sub(self.min_confirmations as u64);
+        let cursor = ExportCursor {
+            last_block: self.rpc.get_block_hash(height)?,
+            height,
+            immature: Vec::new(),
+        };
+
+        Ok((self.all_transactions()?, Vec::new(), cursor))
+    }
+
+    /// Entries of the held back coinbase transactions that matured since, and
+    /// the txids still immature. Orphaned ones never paid out and are dropped.
+    fn matured(
+        &self,
+        held: &[Txid],
+    ) -> Result<(Vec<ListTransactionResult>, Vec<Txid>), Box<dyn Error>> {
+        let mut matured = Vec::new();
+        let mut immature = Vec::new();
+
+        for txid in held {
+            let tx = self
+                .rpc
+                .get_transaction(txid, Some(self.include_watchonly))?;
+            for detail in tx.details {
+                match detail.category {
+                    GetTransactionResultDetailCategory::Immature => immature.push(*txid),
+                    GetTransactionResultDetailCategory::Orphan => {
+                        println!("Warning: coinbase {} was orphaned, not exported", txid)
+                    }
+                    _ => matured.push(ListTransactionResult {
+                        info: tx.info.clone(),
+                        detail,
+                        trusted: None,
+                        comment: None,
+                    }),
+                }
+            }
+        }
+        immature.dedup();
+
+        Ok((matured, immature))
+    }
+
+    /// Pages through `listtransactions`, returned oldest first.
+    fn all_transactions(&self) -> Result<Vec<ListTransactionResult>, Box<dyn Error>> {
+        let mut transactions = Vec::new();
+        let mut skip = 0;
+
+        loop {
+            let page = self.rpc.list_transactions(
+                None,
+                Some(PAGE_SIZE),
+                Some(skip),
+                Some(self.include_watchonly),
+            )?;
+            let len = page.len();
+            transactions.push(page);
+            skip += len;
+
+            if len < PAGE_SIZE {
+                break;
+            }
+            println!("Fetched {} wallet entries", skip);
+        }
+
+        // Each page is oldest first, but pages go from the newest backwards.
+        Ok(transactions.into_iter().rev().flatten().collect())
+    }
+}
+
+fn category(tx: &ListTransactionResult) -> &'static str {
+    match tx.detail.category {
+        GetTransactionResultDetailCategory::Send => "send",
+        GetTransactionResultDetailCategory::Receive => "receive",
+        GetTransactionResultDetailCategory::Generate => "generate",
+        GetTransactionResultDetailCategory::Immature => "immature",
+        GetTransactionResultDetailCategory::Orphan => "orphan",
+    }
+}
+
+fn csv_row(tx: &ListTransactionResult) -> String {
+    let address = tx
+        .detail
+        .address
+        .as_ref()
+        .map(|address| address.clone().assume_checked().to_string());
+    let block_time = tx.info.blocktime.unwrap_or(tx.info.time);
+
+    let fields = [
+        tx.info.txid.to_string(),
+        tx.detail.vout.to_string(),
+        category(tx).to_string(),
+        address.unwrap_or_default(),
+        tx.detail.label.clone().unwrap_or_default(),
+        btc(tx.detail.amount),
+        tx.detail.amount.to_sat().to_string(),
+        tx.detail.fee.map(btc).unwrap_or_default(),
+        tx.detail
+            .fee
+            .map(|fee| fee.to_sat().to_string())
+            .unwrap_or_default(),
+        tx.info.confirmations.to_string(),
+        tx.info
+            .blockheight
+            .map(|height| height.to_string())
+            .unwrap_or_default(),
+        tx.info
+            .blockhash
+            .map(|hash| hash.to_string())
+            .unwrap_or_default(),
+        block_time.to_string(),
+        format_utc(block_time),
+    ];
+
+    fields
+        .iter()
+        .map(|field| escape(field))
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+fn write_rows(csv_path: &str, rows: &[String]) -> Result<(), Box<dyn Error>> {
+    let is_new = fs::metadata(csv_path).map_or(true, |meta| meta.len() == 0);
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(csv_path)?;
+
+    if is_new {
+        writeln!(file, "{CSV_HEADER}")?;
+    }
+    for row in rows {
+        writeln!(file, "{row}")?;
+    }
+
+    Ok(())
+}
+
+fn btc(amount: SignedAmount) -> String {
+    format!("{:.8}", amount.to_btc())
+}
+
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Formats a unix timestamp as `YYYY-MM-DDTHH:MM:SSZ`.
+fn format_utc(timestamp: u64) -> String {
+    let days = (timestamp / 86_400) as i64;
+    let secs = timestamp % 86_400;
+
+    // Days to civil date, from Howard Hinnant's chrono-compatible algorithms.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs / 3_600,
+        secs % 3_600 / 60,
+        secs % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const TXID: &str = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b";
+
+    fn entry(category: &str, label: &str) -> ListTransactionResult {
+        serde_json::from_value(json!({
+            "address": "bc1q9vza2e8x573nczrlzms0wvx3gsqjx7vavgkx0l",
+            "category": category,
+            "amount": -0.5,
+            "label": label,
+            "vout": 1,
+            "fee": -0.0000141,
+            "confirmations": 6,
+            "blockhash": "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f",
+            "blockheight": 840000,
+            "blockindex": 3,
+            "blocktime": 1713571767,
+            "txid": TXID,
+            "time": 1713571700,
+            "timereceived": 1713571700,
+            "bip125-replaceable": "no",
+            "walletconflicts": []
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn formats_utc_timestamps() {
+        let cases = [
+            (0, "1970-01-01T00:00:00Z"),
+            (1_231_006_505, "2009-01-03T18:15:05Z"),
+            (951_782_400, "2000-02-29T00:00:00Z"),
+            (1_713_571_767, "2024-04-20T00:09:27Z"),
+            (4_107_542_399, "2100-02-28T23:59:59Z"),
+            (4_107_542_400, "2100-03-01T00:00:00Z"),
+        ];
+
+        for (timestamp, expected) in cases {
+            assert_eq!(format_utc(timestamp), expected, "{timestamp}");
+        }
+    }
+
+    #[test]
+    fn escapes_csv_fields() {
+        let cases = [
+            ("plain", "plain"),
+            ("", ""),
+            ("a,b", "\"a,b\""),
+            ("say \"hi\"", "\"say \"\"hi\"\"\""),
+            ("two\nlines", "\"two\nlines\""),
+        ];
+
+        for (field, expected) in cases {
+            assert_eq!(escape(field), expected, "{field}");
+        }
+    }
+
+    #[test]
+    fn normalises_categories() {
+        for name in ["send", "receive", "generate", "immature", "orphan"] {
+            assert_eq!(category(&entry(name, "")), name);
+        }
+    }
+
+    #[test]
+    fn writes_one_row_per_entry() {
+        assert_eq!(
+            csv_row(&entry("send", "rent, march")),
+            format!(
+                "{TXID},1,send,bc1q9vza2e8x573nczrlzms0wvx3gsqjx7vavgkx0l,\"rent, march\",\
+                 -0.50000000,-50000000,-0.00001410,-1410,6,840000,\
+                 000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f,\
+                 1713571767,2024-04-20T00:09:27Z"
+            )
+        );
+        assert_eq!(
+            csv_row(&entry("send", "")).split(',').count(),
+            CSV_HEADER.split(',').count()
+        );
+    }
+}
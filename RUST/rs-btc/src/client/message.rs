@@ -0,0 +1,307 @@
+use bitcoincore_rpc::bitcoin::{
+    absolute::LockTime,
+    base64::{prelude::BASE64_STANDARD, Engine},
+    consensus::{deserialize, serialize},
+    ecdsa,
+    hashes::{sha256, Hash, HashEngine},
+    key::{Keypair, TapTweak},
+    opcodes::{all::OP_RETURN, OP_0},
+    script::Builder,
+    secp256k1::{Message, Secp256k1, XOnlyPublicKey},
+    sighash::{EcdsaSighashType, Prevouts, SighashCache, TapSighashType},
+    sign_message::{signed_msg_hash, MessageSignature},
+    taproot, transaction, Address, Amount, CompressedPublicKey, OutPoint, PrivateKey, PublicKey,
+    ScriptBuf, Sequence, Transaction, TxIn, TxOut, Witness,
+};
+use std::error::Error;
+
+const BIP322_TAG: &[u8] = b"BIP0322-signed-message";
+
+/// Signs locally with a legacy signature for P2PKH and BIP322 simple otherwise.
+pub fn sign(key: &PrivateKey, address: &Address, message: &str) -> Result<String, Box<dyn Error>> {
+    if address.script_pubkey().is_p2pkh() {
+        sign_legacy(key, address, message)
+    } else {
+        sign_bip322_simple(key, address, message)
+    }
+}
+
+/// Signs a message the way `signmessage` does, only valid for P2PKH addresses.
+fn sign_legacy(
+    key: &PrivateKey,
+    address: &Address,
+    message: &str,
+) -> Result<String, Box<dyn Error>> {
+    let secp = Secp256k1::new();
+    if !address.is_related_to_pubkey(&key.public_key(&secp)) {
+        return Err(format!("private key does not belong to {address}").into());
+    }
+
+    let msg = Message::from_digest(signed_msg_hash(message).to_byte_array());
+    let signature = secp.sign_ecdsa_recoverable(&msg, &key.inner);
+
+    Ok(MessageSignature::new(signature, key.compressed).to_base64())
+}
+
+/// Signs a message with the BIP322 "simple" format for P2WPKH and P2TR addresses.
+fn sign_bip322_simple(
+    key: &PrivateKey,
+    address: &Address,
+    message: &str,
+) -> Result<String, Box<dyn Error>> {
+    let secp = Secp256k1::new();
+    let script_pubkey = address.script_pubkey();
+    let to_spend = to_spend_tx(&script_pubkey, message);
+    let mut to_sign = to_sign_tx(&to_spend);
+    let mut cache = SighashCache::new(&to_sign);
+
+    let witness = if script_pubkey.is_p2wpkh() {
+        let pubkey = CompressedPublicKey::from_private_key(&secp, key)?;
+        if ScriptBuf::new_p2wpkh(&pubkey.wpubkey_hash()) != script_pubkey {
+            return Err(format!("private key does not belong to {address}").into());
+        }
+
+        let sighash =
+            cache.p2wpkh_signature_hash(0, &script_pubkey, Amount::ZERO, EcdsaSighashType::All)?;
+        let signature = ecdsa::Signature::sighash_all(
+            secp.sign_ecdsa(&Message::from_digest(sighash.to_byte_array()), &key.inner),
+        );
+
+        Witness::p2wpkh(&signature, &pubkey.0)
+    } else if script_pubkey.is_p2tr() {
+        let keypair = Keypair::from_secret_key(&secp, &key.inner);
+        if ScriptBuf::new_p2tr(&secp, keypair.x_only_public_key().0, None) != script_pubkey {
+            return Err(format!("private key does not belong to {address}").into());
+        }
+
+        let sighash = cache.taproot_key_spend_signature_hash(
+            0,
+            &Prevouts::All(&to_spend.output),
+            TapSighashType::Default,
+        )?;
+        let keypair = keypair.tap_tweak(&secp, None);
+        let signature = secp.sign_schnorr_no_aux_rand(
+            &Message::from_digest(sighash.to_byte_array()),
+            &keypair.to_inner(),
+        );
+
+        Witness::p2tr_key_spend(&taproot::Signature {
+            signature,
+            sighash_type: TapSighashType::Default,
+        })
+    } else {
+        return Err(format!("BIP322 simple signing is not supported for {address}").into());
+    };
+
+    to_sign.input[0].witness = witness;
+    Ok(BASE64_STANDARD.encode(serialize(&to_sign.input[0].witness)))
+}
+
+/// Verifies a legacy or BIP322 simple signature without talking to a node.
+pub fn verify(address: &Address, signature: &str, message: &str) -> Result<bool, Box<dyn Error>> {
+    let bytes = BASE64_STANDARD.decode(signature)?;
+
+    if bytes.len() == 65 {
+        return verify_legacy(address, &bytes, message);
+    }
+
+    verify_bip322_simple(address, &bytes, message)
+}
+
+fn verify_legacy(address: &Address, bytes: &[u8], message: &str) -> Result<bool, Box<dyn Error>> {
+    let secp = Secp256k1::new();
+    let signature = MessageSignature::from_slice(bytes)?;
+    let pubkey = signature.recover_pubkey(&secp, signed_msg_hash(message))?;
+
+    // Some wallets also produce this format for P2WPKH and P2SH-P2WPKH.
+    Ok(address.is_related_to_pubkey(&pubkey))
+}
+
+fn verify_bip322_simple(
+    address: &Address,
+    bytes: &[u8],
+    message: &str,
+) -> Result<bool, Box<dyn Error>> {
+    let secp = Secp256k1::new();
+    let witness: Witness = deserialize(bytes)?;
+    let script_pubkey = address.script_pubkey();
+    let to_spend = to_spend_tx(&script_pubkey, message);
+    let to_sign = to_sign_tx(&to_spend);
+    let mut cache = SighashCache::new(&to_sign);
+
+    if script_pubkey.is_p2wpkh() {
+        let (Some(signature), Some(pubkey), 2) = (witness.nth(0), witness.nth(1), witness.len())
+        else {
+            return Ok(false);
+        };
+        let signature = ecdsa::Signature::from_slice(signature)?;
+        let pubkey = PublicKey::from_slice(pubkey)?;
+
+        if signature.sighash_type != EcdsaSighashType::All || !address.is_related_to_pubkey(&pubkey)
+        {
+            return Ok(false);
+        }
+
+        let sighash =
+            cache.p2wpkh_signature_hash(0, &script_pubkey, Amount::ZERO, EcdsaSighashType::All)?;
+        let msg = Message::from_digest(sighash.to_byte_array());
+
+        Ok(secp
+            .verify_ecdsa(&msg, &signature.signature, &pubkey.inner)
+            .is_ok())
+    } else if script_pubkey.is_p2tr() {
+        let (Some(signature), 1) = (witness.nth(0), witness.len()) else {
+            return Ok(false);
+        };
+        let signature = taproot::Signature::from_slice(signature)?;
+        let output_key = XOnlyPublicKey::from_slice(&script_pubkey.as_bytes()[2..])?;
+
+        let sighash = cache.taproot_key_spend_signature_hash(
+            0,
+            &Prevouts::All(&to_spend.output),
+            signature.sighash_type,
+        )?;
+        let msg = Message::from_digest(sighash.to_byte_array());
+
+        Ok(secp
+            .verify_schnorr(&signature.signature, &msg, &output_key)
+            .is_ok())
+    } else {
+        Err(format!("BIP322 simple verification is not supported for {address}").into())
+    }
+}
+
+fn message_hash(message: &str) -> [u8; 32] {
+    let tag = sha256::Hash::hash(BIP322_TAG);
+    let mut engine = sha256::Hash::engine();
+    engine.input(tag.as_ref());
+    engine.input(tag.as_ref());
+    engine.input(message.as_bytes());
+
+    sha256::Hash::from_engine(engine).to_byte_array()
+}
+
+fn to_spend_tx(script_pubkey: &ScriptBuf, message: &str) -> Transaction {
+    let script_sig = Builder::new()
+        .push_opcode(OP_0)
+        .push_slice(message_hash(message))
+        .into_script();
+
+    Transaction {
+        version: transaction::Version(0),
+        lock_time: LockTime::ZERO,
+        input: vec![TxIn {
+            previous_output: OutPoint::null(),
+            script_sig,
+            sequence: Sequence::ZERO,
+            witness: Witness::new(),
+        }],
+        output: vec![TxOut {
+            value: Amount::ZERO,
+            script_pubkey: script_pubkey.clone(),
+        }],
+    }
+}
+
+fn to_sign_tx(to_spend: &Transaction) -> Transaction {
+    Transaction {
+        version: transaction::Version(0),
+        lock_time: LockTime::ZERO,
+        input: vec![TxIn {
+            previous_output: OutPoint::new(to_spend.compute_txid(), 0),
+            script_sig: ScriptBuf::new(),
+            sequence: Sequence::ZERO,
+            witness: Witness::new(),
+        }],
+        output: vec![TxOut {
+            value: Amount::ZERO,
+            script_pubkey: Builder::new().push_opcode(OP_RETURN).into_script(),
+        }],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoincore_rpc::bitcoin::{hex::DisplayHex, Network};
+
+    // Test vectors from BIP322.
+    const ADDRESS: &str = "bc1q9vza2e8x573nczrlzms0wvx3gsqjx7vavgkx0l";
+    const WIF: &str = "L3VFeEujGtevx9w18HD1fhRbCH67Az2dpCymeRE1SoPK6XQtaN2k";
+    const EMPTY_SIGNATURE: &str = "AkcwRAIgM2gBAQqvZX15ZiysmKmQpDrG83avLIT492QBzLnQIxYCIBaTpOaD20qRlEylyxFSeEA2ba9YOixpX8z46TSDtS40ASECx/EgAxlkQpQ9hYjgGu6EBCPMVPwVIVJqO4XCsMvViHI=";
+    const HELLO_SIGNATURE: &str = "AkcwRAIgZRfIY3p7/DoVTty6YZbWS71bc5Vct9p9Fia83eRmw2QCICK/ENGfwLtptFluMGs2KsqoNSk89pO7F29zJLUx9a/sASECx/EgAxlkQpQ9hYjgGu6EBCPMVPwVIVJqO4XCsMvViHI=";
+
+    fn address(address: &str) -> Address {
+        address.parse::<Address<_>>().unwrap().assume_checked()
+    }
+
+    #[test]
+    fn hashes_messages_with_the_bip322_tag() {
+        assert_eq!(
+            message_hash("").to_lower_hex_string(),
+            "c90c269c4f8fcbe6880f72a721ddfbf1914268a794cbb21cfafee13770ae19f1"
+        );
+        assert_eq!(
+            message_hash("Hello World").to_lower_hex_string(),
+            "f0eb03b1a75ac6d9847f55c624a99169b5dccba2a31f5b23bea77ba270de0a7a"
+        );
+    }
+
+    #[test]
+    fn builds_the_virtual_transactions() {
+        let script_pubkey = address(ADDRESS).script_pubkey();
+        let cases = [
+            (
+                "",
+                "c5680aa69bb8d860bf82d4e9cd3504b55dde018de765a91bb566283c545a99a7",
+                "1e9654e951a5ba44c8604c4de6c67fd78a27e81dcadcfe1edf638ba3aaebaed6",
+            ),
+            (
+                "Hello World",
+                "b79d196740ad5217771c1098fc4a4b51e0535c32236c71f1ea4d61a2d603352b",
+                "88737ae86f2077145f93cc4b153ae9a1cb8d56afa511988c149c5c8c9d93bddf",
+            ),
+        ];
+
+        for (message, to_spend_id, to_sign_id) in cases {
+            let to_spend = to_spend_tx(&script_pubkey, message);
+            assert_eq!(to_spend.compute_txid().to_string(), to_spend_id);
+            assert_eq!(to_sign_tx(&to_spend).compute_txid().to_string(), to_sign_id);
+        }
+    }
+
+    #[test]
+    fn verifies_and_signs_p2wpkh() {
+        let address = address(ADDRESS);
+        let key = PrivateKey::from_wif(WIF).unwrap();
+
+        assert!(verify(&address, EMPTY_SIGNATURE, "").unwrap());
+        assert!(verify(&address, HELLO_SIGNATURE, "Hello World").unwrap());
+        assert!(!verify(&address, HELLO_SIGNATURE, "").unwrap());
+        assert!(!verify(&address, EMPTY_SIGNATURE, "Hello World").unwrap());
+
+        for message in ["", "Hello World"] {
+            let signature = sign(&key, &address, message).unwrap();
+            assert!(verify(&address, &signature, message).unwrap());
+        }
+    }
+
+    #[test]
+    fn signs_and_verifies_legacy() {
+        let secp = Secp256k1::new();
+        let key = PrivateKey::from_wif(WIF).unwrap();
+        let address = Address::p2pkh(key.public_key(&secp), Network::Bitcoin);
+
+        let signature = sign(&key, &address, "Hello World").unwrap();
+        assert_eq!(BASE64_STANDARD.decode(&signature).unwrap().len(), 65);
+        assert!(verify(&address, &signature, "Hello World").unwrap());
+        assert!(!verify(&address, &signature, "Hello world").unwrap());
+
+        // A key of another address is refused.
+        let other = Address::p2pkh(
+            PrivateKey::generate(Network::Bitcoin).public_key(&secp),
+            Network::Bitcoin,
+        );
+        assert!(sign(&key, &other, "Hello World").is_err());
+    }
+}
@@ -0,0 +1,161 @@
+use bitcoincore_rpc::{
+    bitcoin::{
+        absolute::LockTime,
+        block::{Header, Version},
+        hashes::Hash,
+        hex::DisplayHex,
+        opcodes::OP_0,
+        script::{Builder, PushBytesBuf},
+        transaction, Amount, Block, BlockHash, CompactTarget, OutPoint, ScriptBuf, Sequence,
+        Transaction, TxIn, TxMerkleNode, TxOut, Txid, Witness,
+    },
+    json::GetBlockTemplateResult,
+};
+use serde::Serialize;
+use std::error::Error;
+
+/// Value of the coinbase witness reserved value used by the default commitment.
+const WITNESS_RESERVED_VALUE: [u8; 32] = [0u8; 32];
+
+#[derive(Debug, Serialize)]
+pub struct TemplateTransaction {
+    pub txid: Txid,
+    pub fee: Amount,
+    pub weight: usize,
+    pub sigops: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TemplateSummary {
+    pub height: u64,
+    pub previous_block_hash: BlockHash,
+    pub bits: String,
+    pub target: String,
+    pub transactions: Vec<TemplateTransaction>,
+    pub total_fees: Amount,
+    pub total_weight: usize,
+    pub weight_limit: u32,
+    pub subsidy: Amount,
+    pub coinbase_value: Amount,
+}
+
+impl TemplateSummary {
+    pub fn new(template: &GetBlockTemplateResult) -> TemplateSummary {
+        let transactions: Vec<TemplateTransaction> = template
+            .transactions
+            .iter()
+            .map(|tx| TemplateTransaction {
+                txid: tx.txid,
+                fee: tx.fee,
+                weight: tx.weight,
+                sigops: tx.sigops,
+            })
+            .collect();
+
+        let total_fees: Amount = transactions.iter().map(|tx| tx.fee).sum();
+        let total_weight = transactions.iter().map(|tx| tx.weight).sum();
+
+        TemplateSummary {
+            height: template.height,
+            previous_block_hash: template.previous_block_hash,
+            bits: template.bits.to_lower_hex_string(),
+            target: template.target.to_lower_hex_string(),
+            transactions,
+            total_fees,
+            total_weight,
+            weight_limit: template.weight_limit,
+            subsidy: template.coinbase_value - total_fees,
+            coinbase_value: template.coinbase_value,
+        }
+    }
+}
+
+/// Builds a block from a `getblocktemplate` result paying the whole coinbase
+/// value to `payout`, with `coinbase_message` appended after the BIP34 height.
+pub fn assemble_block(
+    template: &GetBlockTemplateResult,
+    payout: ScriptBuf,
+    coinbase_message: &str,
+) -> Result<Block, Box<dyn Error>> {
+    let mut txdata = vec![coinbase_tx(template, payout, coinbase_message)?];
+    for tx in template.transactions.iter() {
+        txdata.push(tx.transaction()?);
+    }
+
+    let bits: [u8; 4] = template.bits.as_slice().try_into()?;
+    let mut block = Block {
+        header: Header {
+            version: Version::from_consensus(template.version as i32),
+            prev_blockhash: template.previous_block_hash,
+            merkle_root: TxMerkleNode::all_zeros(),
+            time: template.current_time as u32,
+            bits: CompactTarget::from_consensus(u32::from_be_bytes(bits)),
+            nonce: 0,
+        },
+        txdata,
+    };
+    block.header.merkle_root = block
+        .compute_merkle_root()
+        .ok_or("block has no transactions")?;
+
+    Ok(block)
+}
+
+/// Increments the nonce until the header meets its own target.
+///
+/// Only practical on regtest, where the target is trivially easy.
+pub fn grind_nonce(block: &mut Block) -> Result<BlockHash, Box<dyn Error>> {
+    let target = block.header.target();
+
+    loop {
+        if let Ok(hash) = block.header.validate_pow(target) {
+            return Ok(hash);
+        }
+
+        block.header.nonce = match block.header.nonce.checked_add(1) {
+            Some(nonce) => nonce,
+            None => return Err("nonce space exhausted, target too hard for local mining".into()),
+        };
+    }
+}
+
+fn coinbase_tx(
+    template: &GetBlockTemplateResult,
+    payout: ScriptBuf,
+    coinbase_message: &str,
+) -> Result<Transaction, Box<dyn Error>> {
+    let mut script_sig = Builder::new().push_int(template.height as i64);
+    script_sig = match coinbase_message.is_empty() {
+        // The coinbase scriptSig must be at least two bytes long.
+        true => script_sig.push_opcode(OP_0),
+        false => script_sig.push_slice(PushBytesBuf::try_from(
+            coinbase_message.as_bytes().to_vec(),
+        )?),
+    };
+
+    let mut output = vec![TxOut {
+        value: template.coinbase_value,
+        script_pubkey: payout,
+    }];
+
+    let mut witness = Witness::new();
+    if !template.default_witness_commitment.is_empty() {
+        output.push(TxOut {
+            value: Amount::ZERO,
+            script_pubkey: template.default_witness_commitment.clone(),
+        });
+        witness.push(WITNESS_RESERVED_VALUE);
+    }
+
+    Ok(Transaction {
+        version: transaction::Version::TWO,
+        lock_time: LockTime::ZERO,
+        input: vec![TxIn {
+            previous_output: OutPoint::null(),
+            script_sig: script_sig.into_script(),
+            sequence: Sequence::MAX,
+            witness,
+        }],
+        output,
+    })
+}
@@ -1,3 +1,12 @@
+pub mod blockfile;
 pub mod clients;
+pub mod filter;
+pub mod history;
+pub mod message;
+pub mod miner;
+pub mod repl;
 pub mod selection;
-pub mod utils;
\ No newline at end of file
+pub mod session;
+pub mod utils;
+pub mod utxo;
+pub mod wallet;
@@ -0,0 +1,423 @@
+use crate::client::{selection::Selector, utils::get_last_result, wallet::display_name};
+
+use rustyline::{
+    completion::{Completer, Pair},
+    error::ReadlineError,
+    highlight::Highlighter,
+    hint::Hinter,
+    history::DefaultHistory,
+    validate::Validator,
+    CompletionType, Config, Context, Editor, Helper,
+};
+use serde_json::Value;
+use std::{collections::HashMap, error::Error, path::PathBuf};
+
+const PROMPT: &str = "rs-btc> ";
+const HISTORY_FILE: &str = ".rs-btc_history";
+/// Maximum edit distance for a typo to still be matched to a command.
+const MAX_TYPO_DISTANCE: usize = 2;
+
+pub enum Command {
+    Call(String, Vec<String>),
+    Exit,
+    Empty,
+}
+
+struct CommandHelper {
+    categories: Vec<String>,
+    functions: Vec<String>,
+    params: HashMap<String, Vec<String>>,
+}
+
+impl CommandHelper {
+    fn new(selector: &Selector) -> CommandHelper {
+        let functions: Vec<String> = selector
+            .get_functions()
+            .iter()
+            .map(|function| function.to_string())
+            .collect();
+
+        let params = functions
+            .iter()
+            .filter_map(|function| {
+                let params = selector.get_params(function)?;
+                let names = params
+                    .iter()
+                    .map(|param| param.split(':').next().unwrap().to_string())
+                    .collect();
+                Some((function.clone(), names))
+            })
+            .collect();
+
+        CommandHelper {
+            categories: selector
+                .get_keys()
+                .iter()
+                .map(|key| key.to_string())
+                .collect(),
+            functions,
+            params,
+        }
+    }
+
+    fn complete_last_result(&self, word: &str) -> Vec<Pair> {
+        let Some(mut value) = get_last_result() else {
+            return Vec::new();
+        };
+
+        let (parent, prefix) = word.rsplit_once('.').unwrap_or(("$last", ""));
+        for field in parent.split('.').skip(1) {
+            let next = match field.parse::<usize>() {
+                Ok(index) => value.get(index),
+                Err(_) => value.get(field),
+            };
+            value = match next {
+                Some(next) => next.clone(),
+                None => return Vec::new(),
+            };
+        }
+
+        let fields: Vec<String> = match value {
+            Value::Object(map) => map.keys().cloned().collect(),
+            Value::Array(items) => (0..items.len()).map(|i| i.to_string()).collect(),
+            _ => Vec::new(),
+        };
+
+        fields
+            .into_iter()
+            .filter(|field| field.starts_with(prefix))
+            .map(|field| {
+                let path = format!("{parent}.{field}");
+                Pair {
+                    display: field,
+                    replacement: path,
+                }
+            })
+            .collect()
+    }
+}
+
+impl Completer for CommandHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let line = &line[..pos];
+        let start = line.rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        let word = &line[start..];
+        let previous: Vec<&str> = line[..start].split_whitespace().collect();
+
+        if word.starts_with("$last") {
+            return Ok((start, self.complete_last_result(word)));
+        }
+
+        let candidates: Vec<&String> = match previous.as_slice() {
+            [] | ["help"] => self
+                .categories
+                .iter()
+                .chain(self.functions.iter())
+                .collect(),
+            [category] if self.categories.iter().any(|key| key == category) => {
+                self.functions.iter().collect()
+            }
+            _ => Vec::new(),
+        };
+
+        let mut pairs: Vec<Pair> = candidates
+            .into_iter()
+            .filter(|candidate| candidate.starts_with(word))
+            .map(|candidate| Pair {
+                display: candidate.clone(),
+                replacement: candidate.clone(),
+            })
+            .collect();
+
+        if previous.is_empty() {
+            for builtin in ["help", "exit"] {
+                if builtin.starts_with(word) {
+                    pairs.push(Pair {
+                        display: builtin.to_string(),
+                        replacement: builtin.to_string(),
+                    });
+                }
+            }
+        }
+
+        Ok((start, pairs))
+    }
+}
+
+impl Hinter for CommandHelper {
+    type Hint = String;
+
+    fn hint(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> Option<String> {
+        if pos < line.len() || !line.ends_with(' ') {
+            return None;
+        }
+
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let (function, typed) = match words.as_slice() {
+            [category, function, args @ ..]
+                if self.categories.iter().any(|key| key == category) =>
+            {
+                (*function, args.len())
+            }
+            [function, args @ ..] => (*function, args.len()),
+            [] => return None,
+        };
+
+        let remaining: Vec<String> = self
+            .params
+            .get(function)?
+            .iter()
+            .skip(typed)
+            .map(|name| format!("<{name}>"))
+            .collect();
+
+        if remaining.is_empty() {
+            None
+        } else {
+            Some(remaining.join(" "))
+        }
+    }
+}
+
+impl Highlighter for CommandHelper {}
+
+impl Validator for CommandHelper {}
+
+impl Helper for CommandHelper {}
+
+/// Line editing prompt with history, completion and typo tolerant commands.
+pub struct Repl {
+    editor: Editor<CommandHelper, DefaultHistory>,
+    history_path: PathBuf,
+    prompt: String,
+}
+
+impl Repl {
+    pub fn new(selector: &Selector) -> Result<Repl, Box<dyn Error>> {
+        let config = Config::builder()
+            .completion_type(CompletionType::List)
+            .auto_add_history(true)
+            .build();
+
+        let mut editor = Editor::with_config(config)?;
+        editor.set_helper(Some(CommandHelper::new(selector)));
+
+        let history_path = match std::env::var("HOME") {
+            Ok(home) => PathBuf::from(home).join(HISTORY_FILE),
+            Err(_) => PathBuf::from(HISTORY_FILE),
+        };
+        // A missing history file just means this is the first session.
+        let _ = editor.load_history(&history_path);
+
+        Ok(Repl {
+            editor,
+            history_path,
+            prompt: PROMPT.to_string(),
+        })
+    }
+
+    /// Shows the active wallet in the prompt, e.g. `rs-btc[savings]> `.
+    pub fn set_wallet(&mut self, wallet: Option<&str>) {
+        self.prompt = match wallet {
+            Some(wallet) => format!("rs-btc[{}]> ", display_name(wallet)),
+            None => PROMPT.to_string(),
+        };
+    }
+
+    pub fn read_command(&mut self, selector: &Selector) -> Result<Command, Box<dyn Error>> {
+        let line = match self.editor.readline(&self.prompt) {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => return Ok(Command::Empty),
+            Err(ReadlineError::Eof) => return Ok(Command::Exit),
+            Err(err) => return Err(err.into()),
+        };
+        self.editor.save_history(&self.history_path)?;
+
+        let mut words = split_args(&line).into_iter();
+        let Some(first) = words.next().map(|word| word.to_lowercase()) else {
+            return Ok(Command::Empty);
+        };
+
+        match first.as_str() {
+            "exit" | "quit" => return Ok(Command::Exit),
+            "help" => {
+                match words.next() {
+                    Some(topic) => print_topic_help(selector, &topic.to_lowercase()),
+                    None => print_usage(selector),
+                }
+                return Ok(Command::Empty);
+            }
+            _ => {}
+        }
+
+        if selector.get_keys().contains(&first.as_str()) {
+            return match words.next() {
+                Some(function) => Ok(self.call(selector, &function.to_lowercase(), words)),
+                None => {
+                    selector.print_with_key(&first);
+                    Ok(Command::Empty)
+                }
+            };
+        }
+
+        Ok(self.call(selector, &first, words))
+    }
+
+    fn call(
+        &self,
+        selector: &Selector,
+        function: &str,
+        args: impl Iterator<Item = String>,
+    ) -> Command {
+        match resolve_function(selector, function) {
+            Some(function) => Command::Call(function, args.collect()),
+            None => Command::Empty,
+        }
+    }
+}
+
+fn resolve_function(selector: &Selector, name: &str) -> Option<String> {
+    if selector.find_function(name).is_some() {
+        return Some(name.to_string());
+    }
+
+    let suggestions = suggest(selector, name);
+    match suggestions.as_slice() {
+        [function] => {
+            println!("Using {function}");
+            Some(function.to_string())
+        }
+        [] => {
+            println!("\ninvalid function {name}, type help to list commands\n");
+            None
+        }
+        _ => {
+            println!("\ninvalid function {name}, did you mean:");
+            for function in suggestions.iter() {
+                println!("      {function}");
+            }
+            None
+        }
+    }
+}
+
+fn suggest<'a>(selector: &'a Selector, name: &str) -> Vec<&'a str> {
+    let mut scored: Vec<(usize, &str)> = selector
+        .get_functions()
+        .into_iter()
+        .map(|function| (strsim::levenshtein(name, function), function))
+        .filter(|(distance, _)| *distance <= MAX_TYPO_DISTANCE)
+        .collect();
+    scored.sort();
+
+    match scored.first() {
+        // A single closest candidate is taken as the intended command.
+        Some((best, _)) if scored.iter().filter(|(d, _)| d == best).count() == 1 => {
+            vec![scored[0].1]
+        }
+        _ => scored.into_iter().map(|(_, function)| function).collect(),
+    }
+}
+
+fn print_usage(selector: &Selector) {
+    println!("\nUsage:");
+    println!("      <function> [args...]");
+    println!("      <category> [function] [args...]");
+    println!("      help <category|function>");
+    println!("      exit");
+    println!("\nArguments can reference the previous result, e.g. $last.hash");
+    println!("\nCategories:");
+    selector.print_all_keys();
+    println!();
+}
+
+fn print_topic_help(selector: &Selector, topic: &str) {
+    if selector.get_keys().contains(&topic) {
+        selector.print_with_key(topic);
+        return;
+    }
+
+    if let Some(function) = resolve_function(selector, topic) {
+        selector.print_function_help(&function);
+    }
+}
+
+/// Splits a line on whitespace, keeping double quoted arguments together.
+fn split_args(line: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+
+    for c in line.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    args.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        args.push(current);
+    }
+
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn selector() -> Selector<'static> {
+        let mut selector = Selector::new();
+        selector.insert_block_kv();
+        selector.insert_wallet_kv();
+        selector
+    }
+
+    #[test]
+    fn splits_on_whitespace_outside_quotes() {
+        assert_eq!(
+            split_args("  sign bc1qxyz  \"hello  world\" wallet "),
+            ["sign", "bc1qxyz", "hello  world", "wallet"]
+        );
+        assert_eq!(split_args("label \"\"x"), ["label", "x"]);
+        assert!(split_args("   ").is_empty());
+    }
+
+    #[test]
+    fn suggests_the_closest_function() {
+        let selector = selector();
+        assert_eq!(suggest(&selector, "get_blok_count"), ["get_block_count"]);
+        assert!(suggest(&selector, "something_else").is_empty());
+
+        assert_eq!(
+            resolve_function(&selector, "get_block_count").as_deref(),
+            Some("get_block_count")
+        );
+        assert_eq!(
+            resolve_function(&selector, "load_walet").as_deref(),
+            Some("load_wallet")
+        );
+    }
+
+    #[test]
+    fn ties_are_not_resolved() {
+        let selector = selector();
+        // One edit away from both get_balance and get_balances.
+        assert_eq!(
+            suggest(&selector, "get_balancex"),
+            ["get_balance", "get_balances"]
+        );
+        assert_eq!(resolve_function(&selector, "get_balancex"), None);
+    }
+}
@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-const KEYS: [&str; 8] = [
+const KEYS: [&str; 9] = [
     "block",
     "blockchain",
     "transaction",
@@ -9,11 +9,13 @@ const KEYS: [&str; 8] = [
     "utils",
     "psbt",
     "mempool",
+    "offline",
 ];
 
 pub struct Selector<'a> {
-    keys: [&'a str; 8],
+    keys: [&'a str; 9],
     function_selector: HashMap<&'a str, Vec<&'a str>>,
+    function_params: HashMap<&'a str, Vec<&'a str>>,
 }
 
 impl<'a> Selector<'a> {
@@ -21,6 +23,7 @@ impl<'a> Selector<'a> {
         Selector {
             keys: KEYS,
             function_selector: HashMap::new(),
+            function_params: HashMap::new(),
         }
     }
 
@@ -50,20 +53,32 @@ impl<'a> Selector<'a> {
             "list_since_block:w",
             "generate:w:m",
             "generate_to_address:w:m",
+            "mine:w",
         ];
 
         self.function_selector.insert("block", functions);
     }
 
     pub fn insert_blockchain_kv(&mut self) {
-        let functions = vec!["rescan_blockchain:w", "scan_tx_out_set_blocking:W"];
+        let functions = vec![
+            "rescan_blockchain:w",
+            "scan_tx_out_set_blocking:W",
+            "scan_block_filters:r",
+            "utxo_set_info:r:m",
+            "scan_utxos:r",
+            "scan_status:r",
+            "abort_scan:w",
+        ];
 
         self.function_selector.insert("blockchain", functions);
     }
 
     pub fn insert_transaction_kv(&mut self) {
         let functions = vec![
+            "get_raw_transaction:r",
+            "get_raw_transaction_hex:r",
             "get_raw_transaction_info:r:m",
+            "get_transaction:r",
             "get_tx_out_proof:r",
             "get_tx_out_set_info:r",
             "decode_raw_transaction:r:m",
@@ -87,20 +102,29 @@ impl<'a> Selector<'a> {
             "list_wallet_dir:r",
             "get_wallet_info:r",
             "dump_private_key:r",
+            "get_balance:r",
             "get_balances:r:m",
+            "list_transactions:r",
+            "export_history:r",
             "get_received_by_address:r",
             "list_unspent:r:m",
             "list_received_by_address:r",
             "get_new_address:r",
             "get_raw_change_address:r",
             "get_address_info:r",
+            "wallet_overview:r:m",
+            "create_wallet:w",
             "load_wallet:w",
+            "unload_wallet:w",
+            "use_wallet:w",
             "backup_wallet:w",
+            "schedule_backups:w",
+            "backup_status:r",
+            "stop_backups:w",
             "encrypt_wallet:w",
             "set_label:w",
             "lock_unspent:w",
             "unlock_unspent:w",
-            "backup_wallet:w",
             "unlock_unspent_all:w",
         ];
 
@@ -120,6 +144,9 @@ impl<'a> Selector<'a> {
             "get_peer_info:r",
             "ping:r",
             "get_node_addresses:r",
+            "get_net_totals:r",
+            "get_network_hash_ps:r",
+            "uptime:r",
             "add_node:w",
             "remove_node:w",
             "onetry_node:w",
@@ -137,7 +164,8 @@ impl<'a> Selector<'a> {
     pub fn insert_utils_kv(&mut self) {
         let functions = vec![
             "stop:w:m",
-            "verify_message:r",
+            "sign:w",
+            "verify:r",
             "derive_addresses:r",
             "get_descriptor_info:r",
             "get_index_info:r",
@@ -171,7 +199,267 @@ impl<'a> Selector<'a> {
         self.function_selector.insert("mempool", functions);
     }
 
-    pub fn get_keys(&self) -> [&str; 8] {
+    pub fn insert_offline_kv(&mut self) {
+        let functions = vec!["read_block_files:r", "block_file_stats:r"];
+
+        self.function_selector.insert("offline", functions);
+    }
+
+    pub fn insert_params(&mut self) {
+        let block_number = vec!["block_number:height of the block"];
+        for function in [
+            "get_block",
+            "get_block_hex",
+            "get_block_info",
+            "get_block_header",
+            "get_block_hash",
+            "get_block_stats",
+            "get_block_header_info",
+            "get_block_filter",
+            "invalidate_block",
+            "reconsider_block",
+        ] {
+            self.function_params.insert(function, block_number.clone());
+        }
+
+        let node_address = vec!["node_address:ip:port of the node"];
+        for function in ["add_node", "remove_node", "onetry_node", "disconnect_node"] {
+            self.function_params.insert(function, node_address.clone());
+        }
+
+        let raw_transaction = vec![
+            "transaction id:txid of the transaction",
+            "block_number:height of the block containing it",
+        ];
+        for function in [
+            "get_raw_transaction",
+            "get_raw_transaction_hex",
+            "get_raw_transaction_info",
+        ] {
+            self.function_params
+                .insert(function, raw_transaction.clone());
+        }
+
+        let block_files = vec![
+            "blocks directory:path to the datadir blocks folder",
+            "network:main, test, signet or regtest",
+            "number of blocks:0 reads every block",
+        ];
+        for function in ["read_block_files", "block_file_stats"] {
+            self.function_params.insert(function, block_files.clone());
+        }
+
+        let params = [
+            ("dump_private_key", vec!["address:bitcoin address"]),
+            ("encrypt_wallet", vec!["passphrase:new wallet passphrase"]),
+            (
+                "get_received_by_address",
+                vec![
+                    "address:bitcoin address",
+                    "minimum confirmation:number of confirmations",
+                ],
+            ),
+            (
+                "set_label",
+                vec!["address:bitcoin address", "label:new label"],
+            ),
+            ("key_pool_refill", vec!["new_size:new key pool size"]),
+            ("get_address_info", vec!["address:bitcoin address"]),
+            (
+                "generate",
+                vec![
+                    "block number:number of blocks to generate",
+                    "max tries:maximum iterations",
+                ],
+            ),
+            (
+                "get_mempool_entry",
+                vec!["transaction id:txid in the mempool"],
+            ),
+            ("disconnect_node_by_id", vec!["node id:peer id"]),
+            ("get_added_node_info", vec!["node:added node address"]),
+            ("set_network_active", vec!["state:0 for false, 1 for true"]),
+            (
+                "wait_for_new_block",
+                vec![
+                    "block_number:height of the block to wait for",
+                    "time out:milliseconds to wait",
+                ],
+            ),
+            ("get_descriptor_info", vec!["desc:output descriptor"]),
+            ("join_psbt", vec!["psbt array:count followed by each psbt"]),
+            (
+                "combine_psbt",
+                vec!["psbt array:count followed by each psbt"],
+            ),
+            (
+                "combine_raw_transaction",
+                vec!["raw transaction array:count followed by each hex"],
+            ),
+            (
+                "finalize_psbt",
+                vec!["psbt:base64 psbt", "extract:0 for false, 1 for true"],
+            ),
+            ("submit_block_hex", vec!["block hex:serialized block"]),
+            (
+                "create_wallet",
+                vec![
+                    "wallet:wallet name",
+                    "descriptors:1 for a descriptor wallet, 0 for legacy",
+                    "disable private keys:0 for false, 1 for true",
+                    "blank:0 for false, 1 for true",
+                    "passphrase:wallet passphrase, empty for none",
+                    "avoid reuse:0 for false, 1 for true",
+                ],
+            ),
+            (
+                "scan_tx_out_set_blocking",
+                vec!["descriptor array:count followed by each descriptor"],
+            ),
+            ("utxo_set_info", vec!["hash type:muhash or none"]),
+            (
+                "scan_utxos",
+                vec!["descriptor array:count followed by each descriptor or address"],
+            ),
+            ("load_wallet", vec!["wallet:wallet name"]),
+            ("unload_wallet", vec!["wallet:wallet name"]),
+            (
+                "use_wallet",
+                vec!["wallet:loaded wallet, - for the node endpoint"],
+            ),
+            ("backup_wallet", vec!["destination:path on the node"]),
+            (
+                "schedule_backups",
+                vec![
+                    "directory:backup directory on the node",
+                    "interval:seconds between backups",
+                    "keep:backups to keep per wallet",
+                    "rounds:0 to run until stop_backups",
+                ],
+            ),
+            (
+                "export_history",
+                vec![
+                    "csv path:file the rows are appended to",
+                    "cursor path:file remembering the last exported block",
+                    "minimum confirmations:only export transactions this deep",
+                    "include_watchonly:0 for false, 1 for true",
+                ],
+            ),
+            (
+                "list_since_block",
+                vec![
+                    "block_number:height to list since",
+                    "target confirmations:number of confirmations",
+                    "include_watchonly:0 for false, 1 for true",
+                    "include_removed:0 for false, 1 for true",
+                ],
+            ),
+            (
+                "get_tx_out_proof",
+                vec![
+                    "transaction id array:count followed by each txid",
+                    "block_number:height of the block containing them",
+                ],
+            ),
+            (
+                "list_received_by_address",
+                vec![
+                    "address:bitcoin address",
+                    "minimum confirmation:number of confirmations",
+                    "include empty:0 for false, 1 for true",
+                    "include watchonly:0 for false, 1 for true",
+                ],
+            ),
+            (
+                "decode_raw_transaction",
+                vec![
+                    "transaction:raw transaction hex",
+                    "is witness:0 for false, 1 for true",
+                ],
+            ),
+            (
+                "sign",
+                vec![
+                    "address:address to sign for",
+                    "message:message to sign",
+                    "private key:WIF key, or wallet to use signmessage",
+                ],
+            ),
+            (
+                "verify",
+                vec![
+                    "address:address that signed",
+                    "signature:base64 legacy or BIP322 simple signature",
+                    "message:signed message",
+                ],
+            ),
+            ("get_new_address", vec!["label:address label"]),
+            (
+                "get_balance",
+                vec![
+                    "minimum confirmation:number of confirmations",
+                    "include_watchonly:0 for false, 1 for true",
+                ],
+            ),
+            (
+                "get_transaction",
+                vec![
+                    "transaction id:txid of a wallet transaction",
+                    "include_watchonly:0 for false, 1 for true",
+                ],
+            ),
+            (
+                "list_transactions",
+                vec![
+                    "label:only transactions with this label, * for all",
+                    "count:number of transactions",
+                    "skip:number of most recent transactions to skip",
+                    "include_watchonly:0 for false, 1 for true",
+                ],
+            ),
+            (
+                "get_network_hash_ps",
+                vec![
+                    "number of blocks:blocks to average over",
+                    "height:block height to estimate at",
+                ],
+            ),
+            (
+                "generate_to_address",
+                vec![
+                    "block_number:number of blocks to generate",
+                    "address:bitcoin address",
+                ],
+            ),
+            ("get_node_addresses", vec!["count:number of addresses"]),
+            (
+                "mine",
+                vec![
+                    "payout address:address receiving the coinbase value",
+                    "coinbase message:text added to the coinbase scriptSig",
+                ],
+            ),
+            (
+                "rescan_blockchain",
+                vec!["start from:start height", "stop from:stop height"],
+            ),
+            (
+                "scan_block_filters",
+                vec![
+                    "start height:first block to scan",
+                    "stop height:last block to scan",
+                    "addresses or scripts:count followed by each address or script hex",
+                ],
+            ),
+        ];
+
+        for (function, param) in params {
+            self.function_params.insert(function, param);
+        }
+    }
+
+    pub fn get_keys(&self) -> [&str; 9] {
         self.keys
     }
 
@@ -179,6 +467,60 @@ impl<'a> Selector<'a> {
         self.function_selector.get(key)
     }
 
+    pub fn get_params(&self, function: &str) -> Option<&Vec<&str>> {
+        self.function_params.get(function)
+    }
+
+    pub fn get_functions(&self) -> Vec<&str> {
+        let mut functions: Vec<&str> = self
+            .function_selector
+            .values()
+            .flatten()
+            .map(|item| item.split(':').next().unwrap())
+            .collect();
+        functions.sort();
+        functions.dedup();
+
+        functions
+    }
+
+    pub fn find_function(&self, function: &str) -> Option<(&str, &str)> {
+        for (key, values) in &self.function_selector {
+            for item in values.iter() {
+                if item.split(':').next() == Some(function) {
+                    return Some((key, item));
+                }
+            }
+        }
+
+        None
+    }
+
+    pub fn print_function_help(&self, function: &str) {
+        let Some((key, item)) = self.find_function(function) else {
+            println!("\nunknown function {function}\n");
+            return;
+        };
+
+        let f_type = match item.split(':').nth(1) {
+            Some("r") => "Read",
+            _ => "Write",
+        };
+
+        println!("\n{function} ({key}, {f_type})");
+        match self.get_params(function) {
+            Some(params) => {
+                println!("Parameters:");
+                for param in params.iter() {
+                    let (name, description) = param.split_once(':').unwrap_or((param, ""));
+                    println!("    <{name}> {description}");
+                }
+            }
+            None => println!("No parameters"),
+        }
+        println!();
+    }
+
     pub fn print_with_key(&self, key: &str) {
         for values in &self.function_selector.get(key.trim()) {
             for item in values.iter() {
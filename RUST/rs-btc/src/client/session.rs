@@ -0,0 +1,289 @@
+use bitcoincore_rpc::{
+    jsonrpc::{
+        self, error::RpcError, simple_http::SimpleHttpTransport, Request, Response, Transport,
+    },
+    Auth, Client,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use std::{
+    collections::VecDeque,
+    error::Error,
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// Methods whose params or results carry passphrases or private keys.
+const SECRET_METHODS: [&str; 14] = [
+    "createwallet",
+    "dumpprivkey",
+    "dumpwallet",
+    "encryptwallet",
+    "importdescriptors",
+    "importmulti",
+    "importprivkey",
+    "importwallet",
+    "listdescriptors",
+    "sethdseed",
+    "signmessagewithprivkey",
+    "signrawtransactionwithkey",
+    "walletpassphrase",
+    "walletpassphrasechange",
+];
+
+/// One line of a session file.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordedCall {
+    url: String,
+    method: String,
+    params: Option<Box<RawValue>>,
+    result: Option<Box<RawValue>>,
+    error: Option<RpcError>,
+    /// Set when the node could not be reached at all.
+    transport_error: Option<String>,
+    /// Params and result were left out because they carry secrets.
+    #[serde(default)]
+    redacted: bool,
+}
+
+impl RecordedCall {
+    fn new(url: &str, request: &Request, response: &Result<Response, jsonrpc::Error>) -> Self {
+        let mut call = RecordedCall {
+            url: url.to_string(),
+            method: request.method.to_string(),
+            params: request.params.map(|params| params.to_owned()),
+            result: None,
+            error: None,
+            transport_error: None,
+            redacted: false,
+        };
+        match response {
+            Ok(response) => {
+                call.result = response.result.clone();
+                call.error = response.error.clone();
+            }
+            Err(err) => call.transport_error = Some(err.to_string()),
+        }
+
+        if SECRET_METHODS.contains(&request.method) {
+            call.params = None;
+            call.result = None;
+            call.redacted = true;
+        }
+        call
+    }
+
+    /// Redacted calls have no params left to compare, so only the method counts.
+    fn matches(&self, url: &str, request: &Request) -> bool {
+        self.url == url
+            && self.method == request.method
+            && (self.redacted
+                || self.params.as_ref().map(|params| params.get())
+                    == request.params.map(|params| params.get()))
+    }
+}
+
+/// Decides where the RPC clients created by `Clients` send their requests.
+pub enum Session {
+    Live,
+    /// Talk to the node and append every call to a JSON-lines file.
+    Record(Arc<Mutex<File>>),
+    /// Answer from a recorded file without any node.
+    Replay(Arc<Mutex<VecDeque<RecordedCall>>>),
+}
+
+impl Session {
+    /// Reads `--record <file>` or `--replay <file>` from the command line.
+    pub fn from_args(mut args: impl Iterator<Item = String>) -> Result<Session, Box<dyn Error>> {
+        let session = match args.next().as_deref() {
+            None => Session::Live,
+            Some("--record") => {
+                let path = args.next().ok_or("--record needs a file")?;
+                let file = OpenOptions::new().create(true).append(true).open(path)?;
+                Session::Record(Arc::new(Mutex::new(file)))
+            }
+            Some("--replay") => {
+                let path = args.next().ok_or("--replay needs a file")?;
+                let calls = fs::read_to_string(path)?
+                    .lines()
+                    .filter(|line| !line.trim().is_empty())
+                    .map(serde_json::from_str)
+                    .collect::<Result<VecDeque<RecordedCall>, _>>()?;
+                Session::Replay(Arc::new(Mutex::new(calls)))
+            }
+            Some(arg) => {
+                return Err(format!("unknown argument {arg}, use --record or --replay").into())
+            }
+        };
+
+        Ok(session)
+    }
+
+    /// Builds a client for `url`, `timeout` replaces the default 15 seconds.
+    pub fn client(
+        &self,
+        url: &str,
+        auth: &Auth,
+        timeout: Option<Duration>,
+    ) -> Result<Client, Box<dyn Error>> {
+        if let Session::Replay(calls) = self {
+            let transport = ReplayTransport {
+                url: url.to_string(),
+                calls: calls.clone(),
+            };
+            return Ok(Client::from_jsonrpc(jsonrpc::Client::with_transport(
+                transport,
+            )));
+        }
+
+        let (user, password) = auth.clone().get_user_pass()?;
+        let mut builder = SimpleHttpTransport::builder().url(url)?;
+        if let Some(user) = user {
+            builder = builder.auth(user, password);
+        }
+        if let Some(timeout) = timeout {
+            builder = builder.timeout(timeout);
+        }
+        let inner = builder.build();
+
+        let client = match self {
+            Session::Record(log) => jsonrpc::Client::with_transport(RecordingTransport {
+                inner,
+                url: url.to_string(),
+                log: log.clone(),
+            }),
+            _ => jsonrpc::Client::with_transport(inner),
+        };
+        Ok(Client::from_jsonrpc(client))
+    }
+}
+
+struct RecordingTransport {
+    inner: SimpleHttpTransport,
+    url: String,
+    log: Arc<Mutex<File>>,
+}
+
+impl RecordingTransport {
+    fn record(&self, call: &RecordedCall) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let line = serde_json::to_string(call)?;
+        let mut log = self.log.lock().map_err(|_| "session file lock poisoned")?;
+        writeln!(log, "{line}")?;
+        log.flush()?;
+        Ok(())
+    }
+}
+
+impl Transport for RecordingTransport {
+    fn send_request(&self, request: Request) -> Result<Response, jsonrpc::Error> {
+        let response = self.inner.send_request(request.clone());
+
+        let call = RecordedCall::new(&self.url, &request, &response);
+        self.record(&call).map_err(jsonrpc::Error::Transport)?;
+
+        response
+    }
+
+    fn send_batch(&self, requests: &[Request]) -> Result<Vec<Response>, jsonrpc::Error> {
+        requests
+            .iter()
+            .map(|request| self.send_request(request.clone()))
+            .collect()
+    }
+
+    fn fmt_target(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (recording)", self.url)
+    }
+}
+
+/// Serves recorded calls in order, each one answering a single matching request.
+struct ReplayTransport {
+    url: String,
+    calls: Arc<Mutex<VecDeque<RecordedCall>>>,
+}
+
+impl Transport for ReplayTransport {
+    fn send_request(&self, request: Request) -> Result<Response, jsonrpc::Error> {
+        let mut calls = self
+            .calls
+            .lock()
+            .map_err(|_| jsonrpc::Error::Transport("session lock poisoned".into()))?;
+
+        let call = calls
+            .iter()
+            .position(|call| call.matches(&self.url, &request))
+            .and_then(|index| calls.remove(index))
+            .ok_or_else(|| {
+                jsonrpc::Error::Transport(
+                    format!(
+                        "no recorded response for {} on {}",
+                        request.method, self.url
+                    )
+                    .into(),
+                )
+            })?;
+
+        if let Some(err) = call.transport_error {
+            return Err(jsonrpc::Error::Transport(err.into()));
+        }
+        if call.redacted {
+            return Err(jsonrpc::Error::Transport(
+                format!(
+                    "{} was recorded without its secrets and cannot be replayed",
+                    call.method
+                )
+                .into(),
+            ));
+        }
+
+        Ok(Response {
+            result: call.result,
+            error: call.error,
+            id: request.id,
+            jsonrpc: request.jsonrpc.map(|version| version.to_string()),
+        })
+    }
+
+    fn send_batch(&self, requests: &[Request]) -> Result<Vec<Response>, jsonrpc::Error> {
+        requests
+            .iter()
+            .map(|request| self.send_request(request.clone()))
+            .collect()
+    }
+
+    fn fmt_target(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (replay)", self.url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, value::to_raw_value};
+
+    #[test]
+    fn secret_calls_are_recorded_without_params_or_result() {
+        let params = to_raw_value(&json!(["hunter2", 60])).unwrap();
+        let request = Request {
+            method: "walletpassphrase",
+            params: Some(&params),
+            id: json!(1),
+            jsonrpc: Some("2.0"),
+        };
+        let response = Ok(Response {
+            result: Some(to_raw_value(&json!(null)).unwrap()),
+            error: None,
+            id: json!(1),
+            jsonrpc: Some("2.0".to_string()),
+        });
+
+        let call = RecordedCall::new("http://localhost:8332", &request, &response);
+        let line = serde_json::to_string(&call).unwrap();
+        assert!(call.redacted);
+        assert!(!line.contains("hunter2"));
+        assert!(call.matches("http://localhost:8332", &request));
+    }
+}
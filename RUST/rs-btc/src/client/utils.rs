@@ -1,24 +1,89 @@
-use bitcoincore_rpc::bitcoin::{hashes::Hash, hex::FromHex, Address, Network, PubkeyHash, Txid};
+use bitcoincore_rpc::bitcoin::{
+    hashes::Hash, hex::FromHex, Address, Network, PubkeyHash, ScriptBuf, Txid,
+};
 use serde::Serialize;
+use serde_json::Value;
+use std::{cell::RefCell, collections::VecDeque, error::Error};
 
-fn take_input(message: &str) -> String {
-    println!("Please enter {message} : ");
-    let mut input = String::new();
-    std::io::stdin().read_line(&mut input).unwrap();
-    input.trim().to_string()
+thread_local! {
+    static PENDING_INPUT: RefCell<VecDeque<String>> = const { RefCell::new(VecDeque::new()) };
+    static LAST_RESULT: RefCell<Option<Value>> = const { RefCell::new(None) };
 }
 
-pub fn get_block_number() -> u64 {
-    take_input("block_number").parse().unwrap()
+/// Reads the next argument, preferring the ones typed inline with the command.
+pub fn take_input(message: &str) -> String {
+    let input = match PENDING_INPUT.with(|pending| pending.borrow_mut().pop_front()) {
+        Some(input) => input,
+        None => {
+            println!("Please enter {message} : ");
+            let mut input = String::new();
+            std::io::stdin().read_line(&mut input).unwrap();
+            input
+        }
+    };
+
+    resolve_reference(input.trim())
+}
+
+pub fn set_pending_input(args: Vec<String>) {
+    PENDING_INPUT.with(|pending| *pending.borrow_mut() = args.into());
 }
 
-pub fn get_address() -> Address {
+pub fn get_last_result() -> Option<Value> {
+    LAST_RESULT.with(|last| last.borrow().clone())
+}
+
+fn set_last_result<T: Serialize>(data: &T) {
+    let value = serde_json::to_value(data).ok();
+    LAST_RESULT.with(|last| *last.borrow_mut() = value);
+}
+
+/// Expands `$last`, `$last.hash` or `$last.tx.0.txid` into the matching
+/// field of the previous result.
+pub fn resolve_reference(input: &str) -> String {
+    let Some(path) = input.strip_prefix("$last") else {
+        return input.to_string();
+    };
+
+    let Some(mut value) = get_last_result() else {
+        println!("No previous result to reference");
+        return input.to_string();
+    };
+
+    for field in path.split('.').filter(|field| !field.is_empty()) {
+        let next = match field.parse::<usize>() {
+            Ok(index) => value.get(index),
+            Err(_) => value.get(field),
+        };
+
+        value = match next {
+            Some(next) => next.clone(),
+            None => {
+                println!("{field} not found in previous result");
+                return input.to_string();
+            }
+        };
+    }
+
+    match value {
+        Value::String(value) => value,
+        value => value.to_string(),
+    }
+}
+
+pub fn get_block_number() -> Result<u64, Box<dyn Error>> {
+    Ok(take_input("block_number").parse()?)
+}
+
+pub fn get_address() -> Result<Address, Box<dyn Error>> {
     let address = take_input("address");
-    let address_hash: PubkeyHash =
-        PubkeyHash::from_slice(&Vec::from_hex(&address).expect("Invalid address hash"))
-            .expect("Invalid address hash");
+    let address_hash = PubkeyHash::from_slice(&Vec::from_hex(&address)?)?;
 
-    Address::p2pkh(address_hash, Network::Bitcoin)
+    Ok(Address::p2pkh(address_hash, Network::Bitcoin))
+}
+
+pub fn parse_address(input: &str) -> Result<Address, Box<dyn Error>> {
+    Ok(input.parse::<Address<_>>()?.assume_checked())
 }
 
 pub fn get_node_address() -> String {
@@ -26,37 +91,34 @@ pub fn get_node_address() -> String {
 }
 
 pub fn prettify_data<T: Serialize>(data: T) {
+    set_last_result(&data);
     let data = serde_json::to_string_pretty(&data).unwrap();
     println!("{}", data);
 }
 
+pub fn print_plain<T: Serialize + std::fmt::Display>(data: T) {
+    set_last_result(&data);
+    println!("{}", data);
+}
+
 pub fn print_object<T: Serialize>(data: Vec<T>) {
     for item in data.iter() {
         prettify_data(item);
     }
+    set_last_result(&data);
 }
 
-pub fn get_bool(message: &str) -> bool {
-    let result;
-    loop {
-        let input = take_input(message).parse().unwrap();
-        result = match input {
-            0 => false,
-            1 => true,
-            _ => {
-                println!("Invalid input");
-                continue;
-            }
-        };
-        break;
+pub fn get_bool(message: &str) -> Result<bool, Box<dyn Error>> {
+    match take_input(message).parse::<u8>()? {
+        0 => Ok(false),
+        1 => Ok(true),
+        input => Err(format!("invalid input {input}, expected 0 or 1").into()),
     }
-
-    result
 }
 
-pub fn get_string_array(message: &str) -> Vec<String> {
+pub fn get_string_array(message: &str) -> Result<Vec<String>, Box<dyn Error>> {
     let mut vecs = Vec::new();
-    let count = take_input(message).parse().unwrap();
+    let count: usize = take_input(message).parse()?;
     let mut i = 0;
     while i < count {
         let input = take_input(&format!("input {i}"));
@@ -64,20 +126,36 @@ pub fn get_string_array(message: &str) -> Vec<String> {
         i += 1;
     }
 
-    vecs
+    Ok(vecs)
 }
 
-pub fn get_txid_array(message: &str) -> Vec<Txid> {
+pub fn get_txid_array(message: &str) -> Result<Vec<Txid>, Box<dyn Error>> {
     let mut vecs = Vec::new();
-    let count = take_input(message).parse().unwrap();
+    let count: usize = take_input(message).parse()?;
     let mut i = 0;
     while i < count {
-        let input = take_input(&format!("input {i}")).parse().unwrap();
+        let input = take_input(&format!("input {i}")).parse()?;
         vecs.push(input);
         i += 1;
     }
 
-    vecs
+    Ok(vecs)
+}
+
+pub fn get_script_array(message: &str) -> Result<Vec<ScriptBuf>, Box<dyn Error>> {
+    get_string_array(message)?
+        .iter()
+        .map(|input| parse_script(input))
+        .collect()
+}
+
+/// Accepts either an address or a hex encoded scriptPubKey.
+pub fn parse_script(input: &str) -> Result<ScriptBuf, Box<dyn Error>> {
+    if let Ok(address) = input.parse::<Address<_>>() {
+        return Ok(address.assume_checked().script_pubkey());
+    }
+
+    Ok(ScriptBuf::from_hex(input)?)
 }
 
 pub fn print_hashmap<T: IntoIterator<Item = (U, Z)>, U: std::fmt::Debug, Z: std::fmt::Debug>(
@@ -87,3 +165,35 @@ pub fn print_hashmap<T: IntoIterator<Item = (U, Z)>, U: std::fmt::Debug, Z: std:
         println!("{:#?} : {:#?}", key, value);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn resolves_references_to_the_last_result() {
+        set_last_result(&json!({
+            "hash": "00ab",
+            "height": 7,
+            "tx": [{ "txid": "aa" }, { "txid": "bb" }],
+        }));
+
+        assert_eq!(resolve_reference("$last.hash"), "00ab");
+        assert_eq!(resolve_reference("$last.height"), "7");
+        assert_eq!(resolve_reference("$last.tx.1.txid"), "bb");
+        assert_eq!(resolve_reference("$last.tx.0"), r#"{"txid":"aa"}"#);
+        // Missing fields and plain arguments stay as typed.
+        assert_eq!(resolve_reference("$last.tx.5.txid"), "$last.tx.5.txid");
+        assert_eq!(resolve_reference("$last.nope"), "$last.nope");
+        assert_eq!(resolve_reference("plain"), "plain");
+    }
+
+    #[test]
+    fn pending_input_is_taken_in_order_and_resolved() {
+        set_last_result(&json!("abc"));
+        set_pending_input(vec![" 12 ".to_string(), "$last".to_string()]);
+        assert_eq!(get_block_number().unwrap(), 12);
+        assert_eq!(take_input("anything"), "abc");
+    }
+}
@@ -0,0 +1,231 @@
+use bitcoincore_rpc::{
+    bitcoin::{address::NetworkUnchecked, Address, Amount, BlockHash, Script, Txid},
+    json::{GetTxOutSetInfoResult, ScanTxOutRequest, ScanTxOutResult},
+    Client, RpcApi,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// Age buckets in blocks, roughly a day, a week, a month and a year.
+const AGE_BUCKETS: [(u64, &str); 4] = [
+    (144, "< 1 day"),
+    (1_008, "< 1 week"),
+    (4_320, "< 1 month"),
+    (52_560, "< 1 year"),
+];
+const OLDEST_BUCKET: &str = ">= 1 year";
+
+#[derive(Debug, Serialize)]
+pub struct UtxoSetSummary {
+    pub height: u64,
+    pub best_block: BlockHash,
+    pub txouts: u64,
+    pub transactions: Option<u64>,
+    pub total_amount: Amount,
+    pub hash: Option<String>,
+    pub disk_size: Option<u64>,
+}
+
+impl UtxoSetSummary {
+    pub fn new(info: GetTxOutSetInfoResult) -> UtxoSetSummary {
+        let hash = info.muhash.or(info.hash_serialized_2);
+
+        UtxoSetSummary {
+            height: info.height,
+            best_block: info.best_block,
+            txouts: info.tx_outs,
+            transactions: info.transactions,
+            total_amount: info.total_amount,
+            hash: hash.map(|hash| hash.to_string()),
+            disk_size: info.disk_size,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct UtxoGroup {
+    pub name: &'static str,
+    pub count: usize,
+    pub amount: Amount,
+}
+
+impl UtxoGroup {
+    fn new(name: &'static str) -> UtxoGroup {
+        UtxoGroup {
+            name,
+            count: 0,
+            amount: Amount::ZERO,
+        }
+    }
+
+    fn add(&mut self, amount: Amount) {
+        self.count += 1;
+        self.amount += amount;
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct MatchedUtxo {
+    pub txid: Txid,
+    pub vout: u32,
+    pub script_type: &'static str,
+    pub amount: Amount,
+    pub height: u64,
+    pub confirmations: u64,
+    pub descriptor: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UtxoReport {
+    pub height: Option<u64>,
+    pub best_block: Option<BlockHash>,
+    pub searched_txouts: Option<u64>,
+    pub total_amount: Amount,
+    pub by_script_type: Vec<UtxoGroup>,
+    pub by_age: Vec<UtxoGroup>,
+    pub utxos: Vec<MatchedUtxo>,
+}
+
+impl UtxoReport {
+    pub fn new(result: ScanTxOutResult) -> UtxoReport {
+        let tip = result.height.unwrap_or_default();
+        let mut by_script_type: BTreeMap<&'static str, UtxoGroup> = BTreeMap::new();
+        let mut by_age: Vec<UtxoGroup> = AGE_BUCKETS
+            .iter()
+            .map(|(_, name)| *name)
+            .chain([OLDEST_BUCKET])
+            .map(UtxoGroup::new)
+            .collect();
+
+        let mut utxos: Vec<MatchedUtxo> = result
+            .unspents
+            .into_iter()
+            .map(|utxo| {
+                let confirmations = tip.saturating_sub(utxo.height) + 1;
+                let script_type = script_type(&utxo.script_pub_key);
+
+                by_script_type
+                    .entry(script_type)
+                    .or_insert_with(|| UtxoGroup::new(script_type))
+                    .add(utxo.amount);
+                by_age[age_bucket(confirmations)].add(utxo.amount);
+
+                MatchedUtxo {
+                    txid: utxo.txid,
+                    vout: utxo.vout,
+                    script_type,
+                    amount: utxo.amount,
+                    height: utxo.height,
+                    confirmations,
+                    descriptor: utxo.descriptor,
+                }
+            })
+            .collect();
+        utxos.sort_by_key(|utxo| utxo.height);
+
+        UtxoReport {
+            height: result.height,
+            best_block: result.best_block_hash,
+            searched_txouts: result.tx_outs,
+            total_amount: result.total_amount,
+            by_script_type: by_script_type.into_values().collect(),
+            by_age,
+            utxos,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ScanStatus {
+    progress: f64,
+}
+
+/// A `scantxoutset` running on its own connection so the prompt stays usable.
+pub struct UtxoScan {
+    handle: JoinHandle<Result<ScanTxOutResult, String>>,
+    started: Instant,
+}
+
+impl UtxoScan {
+    /// `rpc` should have no request timeout, a full scan can take minutes.
+    pub fn start(rpc: Client, requests: Vec<ScanTxOutRequest>) -> UtxoScan {
+        let handle = thread::spawn(move || {
+            rpc.scan_tx_out_set_blocking(&requests)
+                .map_err(|err| err.to_string())
+        });
+
+        UtxoScan {
+            handle,
+            started: Instant::now(),
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    pub fn join(self) -> Result<ScanTxOutResult, Box<dyn Error>> {
+        let result = self.handle.join().map_err(|_| "scan thread panicked")??;
+        if result.success == Some(false) {
+            return Err("scan was aborted".into());
+        }
+
+        Ok(result)
+    }
+}
+
+/// Progress in percent of the running scan, `None` when no scan is running.
+pub fn scan_progress<R: RpcApi>(rpc: &R) -> Result<Option<f64>, Box<dyn Error>> {
+    let status: Option<ScanStatus> = rpc.call("scantxoutset", &["status".into()])?;
+    Ok(status.map(|status| status.progress))
+}
+
+pub fn abort_scan<R: RpcApi>(rpc: &R) -> Result<bool, Box<dyn Error>> {
+    let aborted = rpc.call("scantxoutset", &["abort".into()])?;
+    Ok(aborted)
+}
+
+/// Addresses are wrapped in `addr()`, anything else is passed on as a descriptor.
+pub fn scan_request(input: &str) -> ScanTxOutRequest {
+    match input.parse::<Address<NetworkUnchecked>>() {
+        Ok(address) => ScanTxOutRequest::Single(format!("addr({})", address.assume_checked())),
+        Err(_) => ScanTxOutRequest::Single(input.to_string()),
+    }
+}
+
+fn script_type(script: &Script) -> &'static str {
+    if script.is_p2pkh() {
+        "p2pkh"
+    } else if script.is_p2sh() {
+        "p2sh"
+    } else if script.is_p2wpkh() {
+        "p2wpkh"
+    } else if script.is_p2wsh() {
+        "p2wsh"
+    } else if script.is_p2tr() {
+        "p2tr"
+    } else if script.is_p2pk() {
+        "p2pk"
+    } else if script.is_multisig() {
+        "multisig"
+    } else {
+        "nonstandard"
+    }
+}
+
+/// Index into the `by_age` groups, the last one holds everything older.
+fn age_bucket(confirmations: u64) -> usize {
+    AGE_BUCKETS
+        .iter()
+        .position(|(blocks, _)| confirmations < *blocks)
+        .unwrap_or(AGE_BUCKETS.len())
+}
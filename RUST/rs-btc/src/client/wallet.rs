@@ -0,0 +1,212 @@
+use bitcoincore_rpc::{bitcoin::Amount, Client, RpcApi};
+use serde::Serialize;
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+const BACKUP_EXTENSION: &str = "bak";
+
+#[derive(Debug, Serialize)]
+pub struct WalletOverview {
+    pub name: String,
+    pub trusted: Amount,
+    pub untrusted_pending: Amount,
+    pub immature: Amount,
+    pub watch_only: Option<Amount>,
+    pub tx_count: usize,
+    pub private_keys_enabled: bool,
+    pub encrypted: bool,
+}
+
+impl WalletOverview {
+    /// `rpc` must point at the wallet endpoint, `<url>/wallet/<name>`.
+    pub fn new<R: RpcApi>(name: &str, rpc: &R) -> Result<WalletOverview, Box<dyn Error>> {
+        let balances = rpc.get_balances()?;
+        let info = rpc.get_wallet_info()?;
+
+        Ok(WalletOverview {
+            name: display_name(name).to_string(),
+            trusted: balances.mine.trusted,
+            untrusted_pending: balances.mine.untrusted_pending,
+            immature: balances.mine.immature,
+            watch_only: balances.watchonly.map(|entry| entry.trusted),
+            tx_count: info.tx_count,
+            private_keys_enabled: info.private_keys_enabled,
+            // Only encrypted wallets report an unlock deadline.
+            encrypted: info.unlocked_until.is_some(),
+        })
+    }
+}
+
+/// Name used in prompts and file names for the unnamed default wallet.
+pub fn display_name(wallet: &str) -> &str {
+    if wallet.is_empty() {
+        "default"
+    } else {
+        wallet
+    }
+}
+
+/// Wallet name as a URL path segment, bitcoind decodes it again.
+pub fn url_segment(wallet: &str) -> String {
+    let mut segment = String::new();
+    for byte in wallet.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                segment.push(byte as char)
+            }
+            _ => segment.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    segment
+}
+
+/// Timestamped backups of a fixed set of wallets, taken on their own
+/// connections so the prompt stays usable.
+pub struct BackupSchedule {
+    handle: JoinHandle<()>,
+    stop: Arc<AtomicBool>,
+    log: Arc<Mutex<Vec<String>>>,
+}
+
+impl BackupSchedule {
+    /// `wallets` pairs each wallet name with a client bound to its endpoint.
+    /// Runs `rounds` rounds, or until `stop` when `rounds` is 0.
+    pub fn start(
+        wallets: Vec<(String, Client)>,
+        dir: String,
+        interval: Duration,
+        keep: usize,
+        rounds: usize,
+    ) -> BackupSchedule {
+        let stop = Arc::new(AtomicBool::new(false));
+        let log = Arc::new(Mutex::new(Vec::new()));
+
+        let handle = {
+            let stop = stop.clone();
+            let log = log.clone();
+            thread::spawn(move || {
+                let mut round = 0;
+                while !stop.load(Ordering::Relaxed) {
+                    for (wallet, rpc) in wallets.iter() {
+                        let lines = match backup(rpc, &dir, wallet, keep) {
+                            Ok(lines) => lines,
+                            Err(err) => vec![format!(
+                                "Backup of {} failed : {}",
+                                display_name(wallet),
+                                err
+                            )],
+                        };
+                        log.lock().unwrap().extend(lines);
+                    }
+
+                    round += 1;
+                    if rounds != 0 && round >= rounds {
+                        break;
+                    }
+
+                    let started = Instant::now();
+                    while started.elapsed() < interval && !stop.load(Ordering::Relaxed) {
+                        thread::sleep(Duration::from_millis(200));
+                    }
+                }
+            })
+        };
+
+        BackupSchedule { handle, stop, log }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// Lines logged since the last call.
+    pub fn take_log(&self) -> Vec<String> {
+        std::mem::take(&mut *self.log.lock().unwrap())
+    }
+
+    /// Stops after the backup in progress, if any, and returns the remaining log.
+    pub fn stop(self) -> Result<Vec<String>, Box<dyn Error>> {
+        self.stop.store(true, Ordering::Relaxed);
+        let log = self.log;
+        self.handle.join().map_err(|_| "backup thread panicked")?;
+        let lines = std::mem::take(&mut *log.lock().unwrap());
+        Ok(lines)
+    }
+}
+
+fn backup(
+    rpc: &Client,
+    dir: &str,
+    wallet: &str,
+    keep: usize,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let path = backup_path(dir, wallet)?;
+    let path = path.to_str().ok_or("backup path is not valid UTF-8")?;
+    rpc.backup_wallet(Some(path))?;
+
+    let mut lines = vec![format!("Backed up {}", path)];
+    for removed in rotate_backups(dir, wallet, keep)? {
+        lines.push(format!("Removed old backup {}", removed.display()));
+    }
+    Ok(lines)
+}
+
+/// Returns `<dir>/<wallet>-<unix time>.bak`, with path separators in the
+/// wallet name replaced so every backup lands directly in `dir`.
+pub fn backup_path(dir: &str, wallet: &str) -> Result<PathBuf, Box<dyn Error>> {
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let file_name = format!(
+        "{}-{}.{}",
+        backup_prefix(wallet),
+        timestamp,
+        BACKUP_EXTENSION
+    );
+
+    Ok(Path::new(dir).join(file_name))
+}
+
+/// Deletes the oldest backups of `wallet` in `dir` so only `keep` remain.
+///
+/// The node writes backups itself, so this only works when `dir` is on the
+/// same machine as bitcoind.
+pub fn rotate_backups(
+    dir: &str,
+    wallet: &str,
+    keep: usize,
+) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let prefix = format!("{}-", backup_prefix(wallet));
+
+    let mut backups: Vec<(u64, PathBuf)> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == BACKUP_EXTENSION))
+        .filter_map(|path| {
+            let stem = path.file_stem()?.to_str()?;
+            let timestamp = stem.strip_prefix(&prefix)?.parse().ok()?;
+            Some((timestamp, path))
+        })
+        .collect();
+    backups.sort();
+
+    let excess = backups.len().saturating_sub(keep);
+    let mut removed = Vec::new();
+    for (_, path) in backups.into_iter().take(excess) {
+        fs::remove_file(&path)?;
+        removed.push(path);
+    }
+
+    Ok(removed)
+}
+
+fn backup_prefix(wallet: &str) -> String {
+    display_name(wallet).replace(['/', '\\'], "_")
+}
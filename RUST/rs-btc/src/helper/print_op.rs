@@ -25,6 +25,7 @@ pub fn print_block_functions() {
     println!("      list_since_block");
     println!("      generate");
     println!("      generate_to_address");
+    println!("      mine");
     println!("\nMost Used Functions");
     println!("      get_block_count");
     println!("      get_latest_block_hash");
@@ -37,9 +38,15 @@ pub fn print_block_functions() {
 }
 
 pub fn print_blockchain_function() {
+    println!("\nRead Functions");
+    println!("      scan_block_filters");
+    println!("      utxo_set_info");
+    println!("      scan_utxos");
+    println!("      scan_status");
     println!("\nWrite Functions");
     println!("      rescan_blockchain");
     println!("      scan_tx_out_set_blocking");
+    println!("      abort_scan");
 }
 
 pub fn print_tx_functions() {
@@ -68,12 +75,13 @@ pub fn print_tx_functions() {
 pub fn print_utils_functions() {
     println!("\nRead Functions");
     println!("      stop");
-    println!("      verify_message");
+    println!("      verify");
     println!("      derive_addresses");
     println!("      get_descriptor_info");
     println!("      get_index_info");
     println!("      estimate_smart_fee");
     println!("\nWrite Functions");
+    println!("      sign");
     println!("      key_pool_refill");
     println!("\nMost Used Functions");
     println!("      stop");
@@ -92,9 +100,15 @@ pub fn print_wallet_functions() {
     println!("      get_new_address");
     println!("      get_raw_change_address");
     println!("      get_address_info");
+    println!("      wallet_overview");
+    println!("      export_history");
     println!("\nWrite Functions");
+    println!("      create_wallet");
     println!("      load_wallet");
+    println!("      unload_wallet");
+    println!("      use_wallet");
     println!("      backup_wallet");
+    println!("      schedule_backups");
     println!("      encrypt_wallet");
     println!("      set_label");
     println!("      lock_unspent");
@@ -106,6 +120,7 @@ pub fn print_wallet_functions() {
     println!("      get_balances");
     println!("      get_address_info");
     println!("      load_wallet");
+    println!("      wallet_overview");
 }
 
 pub fn print_network_functions() {
@@ -158,6 +173,12 @@ pub fn print_mempool_functions() {
     println!("      get_raw_mempool");
 }
 
+pub fn print_offline_functions() {
+    println!("\nRead Functions");
+    println!("      read_block_files");
+    println!("      block_file_stats");
+}
+
 pub fn print_help_message() {
     println!("\n\nPress 1 help for block");
     println!("Press 2 help for blockchain");
@@ -167,7 +188,8 @@ pub fn print_help_message() {
     println!("Press 6 help for utils");
     println!("Press 7 help for psbt");
     println!("Press 8 help for mempool");
-    println!("Press 9 for exit\n\n");
+    println!("Press 9 help for offline");
+    println!("Press 10 for exit\n\n");
 }
 
 pub fn print_help(input: u8) {
@@ -180,6 +202,7 @@ pub fn print_help(input: u8) {
         6 => print_utils_functions(),
         7 => print_psbt_functions(),
         8 => print_mempool_functions(),
+        9 => print_offline_functions(),
         _ => unreachable!(),
     }
 }
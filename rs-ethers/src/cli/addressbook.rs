@@ -0,0 +1,175 @@
+use super::{print_json, Cli};
+use crate::client::EtherClient;
+use ethers::{types::Address, utils::to_checksum};
+use serde_json::json;
+use std::{collections::BTreeMap, error::Error, fs, path::Path};
+
+/// Address book file when --address-book is not given, next to deployments/.
+pub const DEFAULT_ADDRESS_BOOK: &str = "addressbook.json";
+
+/// Labels per chain id, stored as `{ "<chain id>": { "<label>": "<address>" } }`.
+#[derive(Default)]
+pub struct AddressBook {
+    chains: BTreeMap<String, BTreeMap<String, Address>>,
+}
+
+impl AddressBook {
+    /// The book at `path`, empty when the file does not exist yet.
+    pub fn load(path: &str) -> Result<AddressBook, Box<dyn Error>> {
+        if !Path::new(path).exists() {
+            return Ok(AddressBook::default());
+        }
+        let chains = serde_json::from_str(&fs::read_to_string(path)?)
+            .map_err(|err| format!("address book {path}: {err}"))?;
+        Ok(AddressBook { chains })
+    }
+
+    fn save(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        // Checksummed, so the file doubles as a reference for copying addresses.
+        let chains: BTreeMap<&String, BTreeMap<&String, String>> = self
+            .chains
+            .iter()
+            .map(|(chain, labels)| {
+                let labels = labels
+                    .iter()
+                    .map(|(label, address)| (label, to_checksum(address, None)))
+                    .collect();
+                (chain, labels)
+            })
+            .collect();
+        fs::write(path, serde_json::to_string_pretty(&chains)?)?;
+        Ok(())
+    }
+
+    pub fn labels(&self, chain_id: u64) -> BTreeMap<String, Address> {
+        self.chains
+            .get(&chain_id.to_string())
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+/// `label`, `unlabel` and `labels` on the endpoint's chain, `resolve` and `lookup` through ENS.
+pub async fn run(cli: &Cli, client: &EtherClient) -> Result<(), Box<dyn Error>> {
+    let path = cli.option("--address-book").unwrap_or(DEFAULT_ADDRESS_BOOK);
+
+    match cli.command.as_str() {
+        "resolve" => {
+            let name = cli.arg(0, "name")?;
+            let address = client.resolve_name(name).await?;
+            return print_json(&json!({
+                "name": name,
+                "address": to_checksum(&address, None),
+            }));
+        }
+        "lookup" => {
+            let address = cli.address(cli.arg(0, "address")?)?;
+            let chain_id = client.get_chain_id().await?.as_u64();
+            let labels: Vec<String> = AddressBook::load(path)?
+                .labels(chain_id)
+                .into_iter()
+                .filter(|(_, labeled)| *labeled == address)
+                .map(|(label, _)| label)
+                .collect();
+            return print_json(&json!({
+                "address": to_checksum(&address, None),
+                "name": client.lookup_address(address).await.ok(),
+                "labels": labels,
+            }));
+        }
+        _ => {}
+    }
+
+    let mut book = AddressBook::load(path)?;
+    let chain_id = client.get_chain_id().await?.as_u64();
+    let labels = book.chains.entry(chain_id.to_string()).or_default();
+
+    match cli.command.as_str() {
+        "label" => {
+            let label = cli.arg(0, "label")?;
+            // Labels that parse as addresses or look like ENS names would shadow them.
+            if label.parse::<Address>().is_ok() || label.contains('.') {
+                return Err(format!("label {label} looks like an address or ENS name").into());
+            }
+            let address = cli.address(cli.arg(1, "address")?)?;
+            let previous = labels.insert(label.to_string(), address);
+            book.save(path)?;
+            print_json(&json!({
+                "chain_id": chain_id,
+                "label": label,
+                "address": to_checksum(&address, None),
+                "previous": previous.map(|address| to_checksum(&address, None)),
+            }))
+        }
+        "unlabel" => {
+            let label = cli.arg(0, "label")?;
+            let address = labels
+                .remove(label)
+                .ok_or(format!("no label {label} on chain {chain_id}"))?;
+            book.save(path)?;
+            print_json(&json!({
+                "chain_id": chain_id,
+                "label": label,
+                "address": to_checksum(&address, None),
+            }))
+        }
+        _ => {
+            let labels: BTreeMap<&String, String> = labels
+                .iter()
+                .map(|(label, address)| (label, to_checksum(address, None)))
+                .collect();
+            print_json(&json!({
+                "chain_id": chain_id,
+                "labels": labels,
+            }))
+        }
+    }
+}
+
+/// Warns when a mixed-case address does not match its EIP-55 checksum, a likely typo.
+/// All lower or all upper case addresses carry no checksum and pass.
+pub fn check_checksum(value: &str, address: &Address) {
+    if let Some(warning) = checksum_warning(value, address) {
+        eprintln!("{warning}");
+    }
+}
+
+fn checksum_warning(value: &str, address: &Address) -> Option<String> {
+    let hex = value.trim_start_matches("0x");
+    let lower = hex.chars().any(|c| c.is_ascii_lowercase());
+    let upper = hex.chars().any(|c| c.is_ascii_uppercase());
+
+    let expected = to_checksum(address, None);
+    (lower && upper && hex != &expected[2..]).then(|| {
+        format!(
+            "Warning: {value} does not match its EIP-55 checksum {expected}, check it for typos"
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHECKSUMMED: &str = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+
+    #[test]
+    fn warns_only_on_checksum_mismatch() {
+        let address: Address = CHECKSUMMED.parse().unwrap();
+        let cases = [
+            (CHECKSUMMED, false),
+            ("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed", false),
+            ("0x5AAEB6053F3E94C9B9A09F33669435E7EF1BEAED", false),
+            ("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD", true),
+            ("0x5AAeb6053F3E94C9b9A09f33669435E7Ef1BeAed", true),
+        ];
+
+        for (value, warns) in cases {
+            let warning = checksum_warning(value, &address);
+            assert_eq!(warning.is_some(), warns, "{value}");
+            if let Some(warning) = warning {
+                assert!(warning.contains(CHECKSUMMED), "{warning}");
+            }
+        }
+    }
+}
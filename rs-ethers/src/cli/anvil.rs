@@ -0,0 +1,106 @@
+use super::{print_json, Cli};
+use crate::client::{AnvilSession, EtherClient};
+use ethers::types::{Bytes, U256};
+use serde_json::json;
+use std::{error::Error, fs};
+
+/// `anvil-start`, `anvil-stop` and `anvil-status`: the persistent node, no endpoint needed.
+pub fn run_local(cli: &Cli) -> Result<(), Box<dyn Error>> {
+    match cli.command.as_str() {
+        "anvil-start" => {
+            let session = AnvilSession::start(cli.anvil_config()?)?;
+            print_json(&json!({
+                "endpoint": session.endpoint(),
+                "ws_endpoint": session.ws_endpoint(),
+                "session": session,
+            }))
+        }
+        "anvil-stop" => {
+            let port = cli.anvil_config()?.port;
+            let session = AnvilSession::load(port)?.ok_or("no Anvil session is running")?;
+            session.stop()?;
+            print_json(&json!({
+                "stopped": session.pid,
+                "state": session.config.state,
+            }))
+        }
+        _ => match AnvilSession::load(cli.anvil_config()?.port)? {
+            Some(session) => print_json(&json!({
+                "running": true,
+                "endpoint": session.endpoint(),
+                "ws_endpoint": session.ws_endpoint(),
+                "session": session,
+            })),
+            None => print_json(&json!({ "running": false })),
+        },
+    }
+}
+
+/// Snapshots, time travel and state blobs on an Anvil (or Hardhat) endpoint.
+pub async fn run(cli: &Cli, client: &EtherClient) -> Result<(), Box<dyn Error>> {
+    match cli.command.as_str() {
+        "snapshot" => print_json(&json!({ "snapshot": client.snapshot().await? })),
+        "revert" => {
+            let id: U256 = parse_snapshot(cli.arg(0, "snapshot")?)?;
+            if !client.revert_to_snapshot(id).await? {
+                return Err(format!("snapshot {id:#x} is unknown or was already reverted").into());
+            }
+            print_json(&json!({
+                "reverted": id,
+                "block": client.get_block_count().await?.as_u64(),
+            }))
+        }
+        "increase-time" => {
+            let seconds = cli.arg(0, "seconds")?.parse()?;
+            let offset = client.increase_time(seconds).await?;
+            // The new time only shows in the next block.
+            if !cli.flag("--no-mine") {
+                client.mine(1).await?;
+            }
+            print_json(&json!({
+                "offset": offset,
+                "head": head(client).await?,
+            }))
+        }
+        "mine" => {
+            let blocks = match cli.args.first() {
+                Some(blocks) => blocks.parse()?,
+                None => 1,
+            };
+            client.mine(blocks).await?;
+            print_json(&json!({ "head": head(client).await? }))
+        }
+        "dump-state" => {
+            let path = cli.arg(0, "file")?;
+            let state = client.dump_state().await?;
+            fs::write(path, state.to_string())?;
+            print_json(&json!({ "state": path, "bytes": state.len() }))
+        }
+        _ => {
+            let path = cli.arg(0, "file")?;
+            let state: Bytes = fs::read_to_string(path)?.trim().parse()?;
+            print_json(&json!({ "loaded": client.load_state(state).await? }))
+        }
+    }
+}
+
+/// Snapshot ids as printed by `snapshot` (hex) or as a decimal number.
+fn parse_snapshot(id: &str) -> Result<U256, Box<dyn Error>> {
+    match id.strip_prefix("0x") {
+        Some(hex) => Ok(U256::from_str_radix(hex, 16)?),
+        None => Ok(U256::from_dec_str(id)?),
+    }
+}
+
+/// Number and timestamp of the latest block.
+async fn head(client: &EtherClient) -> Result<serde_json::Value, Box<dyn Error>> {
+    let number = client.get_block_count().await?.as_u64();
+    let block = client
+        .get_block(number)
+        .await?
+        .ok_or(format!("block {number} not found"))?;
+    Ok(json!({
+        "number": number,
+        "timestamp": block.timestamp.as_u64(),
+    }))
+}
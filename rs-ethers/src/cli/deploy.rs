@@ -0,0 +1,86 @@
+use super::{confirm, print_json, Cli};
+use crate::{
+    client::{EtherClient, TxKind},
+    contract::{decode_revert, Artifact},
+};
+use ethers::{types::TransactionRequest, utils::format_units};
+use serde_json::json;
+use std::{error::Error, fs, path::PathBuf};
+
+/// `deploy <source.sol|artifact.json> [constructor args...]`
+pub async fn run(cli: &Cli, client: &EtherClient) -> Result<(), Box<dyn Error>> {
+    let source = cli.arg(0, "source")?;
+    let artifact = Artifact::load(source, cli.option("--contract"))?;
+    let args = match artifact.abi.constructor() {
+        Some(constructor) => cli.resolve_args(&constructor.inputs, &cli.args[1..])?,
+        None => cli.args[1..].to_vec(),
+    };
+
+    let kind: TxKind = cli.option("--type").unwrap_or("1559").parse()?;
+    let request = TransactionRequest::new().data(artifact.deploy_code(&args)?);
+    let value = cli.option("--value").unwrap_or("0");
+    let mut tx = client.create_contract_tx(request, value, kind)?;
+
+    let preview = match client.prepare_tx(&mut tx, false).await {
+        Ok(preview) => preview,
+        Err(err) => {
+            return match err.revert_data() {
+                Some(data) => Err(format!(
+                    "constructor reverted: {}",
+                    decode_revert(&artifact.abi, &data)
+                )
+                .into()),
+                None => Err(err.into()),
+            }
+        }
+    };
+    eprintln!("{}", serde_json::to_string_pretty(&preview)?);
+    if !cli.flag("--yes") && !confirm(&format!("Deploy {}?", artifact.name))? {
+        return Err("aborted".into());
+    }
+
+    let receipt = client
+        .send_raw_tx(tx)
+        .await?
+        .ok_or("deployment transaction was dropped before it was mined")?;
+    if receipt.status.is_some_and(|status| status.is_zero()) {
+        return Err(format!(
+            "deployment reverted in transaction {:?}",
+            receipt.transaction_hash
+        )
+        .into());
+    }
+    let address = receipt
+        .contract_address
+        .ok_or("receipt has no contract address")?;
+    let chain_id = client.get_chain_id().await?.as_u64();
+
+    let record = json!({
+        "contract": artifact.name,
+        "address": address,
+        "chain_id": chain_id,
+        "deployer": receipt.from,
+        "transaction_hash": receipt.transaction_hash,
+        "block_number": receipt.block_number.map(|number| number.as_u64()),
+        "gas_used": receipt.gas_used.map(|gas| gas.to_string()),
+        "effective_gas_price_gwei": receipt
+            .effective_gas_price
+            .map(|price| format_units(price, "gwei"))
+            .transpose()?,
+        "source": source,
+        "constructor_args": args,
+        "metadata": artifact.metadata,
+    });
+
+    let out = match cli.option("--out") {
+        Some(out) => PathBuf::from(out),
+        None => PathBuf::from(format!("deployments/{chain_id}/{}.json", artifact.name)),
+    };
+    if let Some(dir) = out.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&out, serde_json::to_string_pretty(&record)?)?;
+    eprintln!("Deployment written to {}", out.display());
+
+    print_json(&record)
+}
@@ -0,0 +1,278 @@
+use super::{Cli, CliContract};
+use crate::{client::EtherClient, contract::Contracts};
+use ethers::types::{Filter, Log};
+use serde_json::{json, Map, Value};
+use std::{error::Error, time::Duration};
+
+/// Blocks searched back from `--to` when `--from` is not given.
+const DEFAULT_LOG_BLOCKS: u64 = 1_000;
+/// Blocks asked for per `eth_getLogs` before any splitting.
+const DEFAULT_LOG_CHUNK: u64 = 10_000;
+/// Seconds between polls for new blocks in follow mode.
+const DEFAULT_FOLLOW_INTERVAL: u64 = 4;
+
+/// `logs <address> [event]`: historical logs, then new ones with --follow.
+pub async fn run(cli: &Cli, client: &EtherClient) -> Result<(), Box<dyn Error>> {
+    let address = cli.address(cli.arg(0, "address")?)?;
+    let event = cli.args.get(1).map(String::as_str);
+    let contract = contract(cli, client, event)?;
+
+    let mut filter = Filter::new().address(address);
+    let mut columns = Vec::new();
+    if let (Some(name), Some(contract)) = (event, &contract) {
+        let event = contract.event(name)?;
+        filter = filter.topic0(event.signature());
+        columns = event
+            .inputs
+            .iter()
+            .map(|input| input.name.clone())
+            .collect();
+    }
+
+    let printer = LogPrinter {
+        contract: contract.as_ref(),
+        csv: match cli.option("--format").unwrap_or("json") {
+            "json" => false,
+            "csv" => true,
+            format => return Err(format!("unknown format {format}, use json or csv").into()),
+        },
+        columns,
+    };
+
+    let tip = client.get_block_count().await?.as_u64();
+    let to = match cli.option("--to") {
+        None | Some("latest") => tip,
+        Some(number) => number.parse()?,
+    };
+    let from = match cli.option("--from") {
+        Some(number) => number.parse()?,
+        None => to.saturating_sub(DEFAULT_LOG_BLOCKS),
+    };
+    let mut chunk = match cli.option("--chunk") {
+        Some(chunk) => chunk.parse()?,
+        None => DEFAULT_LOG_CHUNK,
+    };
+
+    printer.header();
+    let mut next = from;
+    while next <= to {
+        let (logs, end) = client.get_logs_chunk(&filter, next, to, &mut chunk).await?;
+        printer.print(&logs)?;
+        next = end + 1;
+    }
+
+    if !cli.flag("--follow") {
+        return Ok(());
+    }
+
+    let interval = match cli.option("--interval") {
+        Some(seconds) => seconds.parse()?,
+        None => DEFAULT_FOLLOW_INTERVAL,
+    };
+    loop {
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+
+        let tip = client.get_block_count().await?.as_u64();
+        while next <= tip {
+            let (logs, end) = client
+                .get_logs_chunk(&filter, next, tip, &mut chunk)
+                .await?;
+            printer.print(&logs)?;
+            next = end + 1;
+        }
+    }
+}
+
+/// ABI used for decoding: --abi, or the event itself when given as a signature.
+fn contract(
+    cli: &Cli,
+    client: &EtherClient,
+    event: Option<&str>,
+) -> Result<Option<CliContract>, Box<dyn Error>> {
+    let address = format!("{:?}", cli.address(cli.arg(0, "address")?)?);
+    let address = address.as_str();
+
+    let contract = match (cli.option("--abi"), event) {
+        (Some(path), _) => Contracts::new(client.get_provider(), address, path)?,
+        (None, Some(event)) if event.contains('(') => {
+            Contracts::from_event(client.get_provider(), address, event)?
+        }
+        (None, Some(event)) => {
+            return Err(format!("pass --abi <file> or a full signature for {event}").into())
+        }
+        (None, None) => return Ok(None),
+    };
+    Ok(Some(contract))
+}
+
+struct LogPrinter<'a> {
+    contract: Option<&'a CliContract>,
+    csv: bool,
+    /// Event inputs written as their own CSV columns when a single event is queried.
+    columns: Vec<String>,
+}
+
+impl LogPrinter<'_> {
+    fn header(&self) {
+        if !self.csv {
+            return;
+        }
+
+        let mut header = vec![
+            "block_number",
+            "transaction_hash",
+            "log_index",
+            "address",
+            "event",
+        ];
+        match self.columns.is_empty() {
+            true => header.push("args"),
+            false => header.extend(self.columns.iter().map(String::as_str)),
+        }
+        println!("{}", header.join(","));
+    }
+
+    fn print(&self, logs: &[Log]) -> Result<(), Box<dyn Error>> {
+        for log in logs {
+            let decoded = self.contract.and_then(|contract| contract.decode_log(log));
+            let (event, args) = match decoded {
+                Some((event, fields)) => (Some(event.name.clone()), Value::Object(fields)),
+                None => (
+                    None,
+                    json!({
+                        "topics": log.topics,
+                        "data": log.data,
+                    }),
+                ),
+            };
+
+            match self.csv {
+                true => println!("{}", self.csv_row(log, event, args)),
+                false => println!(
+                    "{}",
+                    serde_json::to_string(&json!({
+                        "block_number": log.block_number.map(|number| number.as_u64()),
+                        "transaction_hash": log.transaction_hash,
+                        "log_index": log.log_index.map(|index| index.as_u64()),
+                        "address": log.address,
+                        "event": event,
+                        "args": args,
+                    }))?
+                ),
+            }
+        }
+        Ok(())
+    }
+
+    fn csv_row(&self, log: &Log, event: Option<String>, args: Value) -> String {
+        let mut fields = vec![
+            log.block_number
+                .map(|number| number.to_string())
+                .unwrap_or_default(),
+            log.transaction_hash
+                .map(|hash| format!("{hash:?}"))
+                .unwrap_or_default(),
+            log.log_index
+                .map(|index| index.to_string())
+                .unwrap_or_default(),
+            format!("{:?}", log.address),
+            event.unwrap_or_default(),
+        ];
+
+        match (self.columns.is_empty(), &args) {
+            (false, Value::Object(args)) => {
+                fields.extend(self.columns.iter().map(|column| field(args, column)))
+            }
+            _ => fields.push(args.to_string()),
+        }
+
+        fields
+            .iter()
+            .map(|field| escape(field))
+            .collect::<Vec<String>>()
+            .join(",")
+    }
+}
+
+fn field(args: &Map<String, Value>, name: &str) -> String {
+    match args.get(name) {
+        Some(Value::String(value)) => value.clone(),
+        Some(value) => value.to_string(),
+        None => String::new(),
+    }
+}
+
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::{Address, Bytes, H256};
+
+    fn log() -> Log {
+        Log {
+            address: Address::repeat_byte(0x11),
+            topics: vec![H256::repeat_byte(0xdd)],
+            data: Bytes::from(vec![0x01, 0x02]),
+            block_number: Some(17.into()),
+            transaction_hash: Some(H256::repeat_byte(0x22)),
+            log_index: Some(3.into()),
+            ..Default::default()
+        }
+    }
+
+    fn printer(columns: &[&str]) -> LogPrinter<'static> {
+        LogPrinter {
+            contract: None,
+            csv: true,
+            columns: columns.iter().map(|column| column.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn escapes_csv_fields() {
+        let cases = [
+            ("plain", "plain"),
+            ("", ""),
+            ("a,b", "\"a,b\""),
+            ("{\"to\":\"0x1\"}", "\"{\"\"to\"\":\"\"0x1\"\"}\""),
+            ("two\nlines", "\"two\nlines\""),
+        ];
+
+        for (field, expected) in cases {
+            assert_eq!(escape(field), expected, "{field}");
+        }
+    }
+
+    #[test]
+    fn writes_event_arguments_as_columns() {
+        let prefix = format!(
+            "17,{:?},3,{:?},Transfer",
+            H256::repeat_byte(0x22),
+            Address::repeat_byte(0x11)
+        );
+        let args = json!({ "from": "0xab", "value": "1000", "memo": "a, b" });
+
+        let row = printer(&["from", "to", "value", "memo"]).csv_row(
+            &log(),
+            Some("Transfer".to_string()),
+            args.clone(),
+        );
+        assert_eq!(row, format!("{prefix},0xab,,1000,\"a, b\""));
+
+        // Without columns, or for logs that were not decoded, args stay one JSON field.
+        let row = printer(&[]).csv_row(&log(), Some("Transfer".to_string()), args);
+        assert_eq!(
+            row,
+            format!("{prefix},\"{{\"\"from\"\":\"\"0xab\"\",\"\"memo\"\":\"\"a, b\"\",\"\"value\"\":\"\"1000\"\"}}\"")
+        );
+        let row = printer(&["from"]).csv_row(&log(), None, json!(null));
+        assert!(row.ends_with(",,null"), "{row}");
+    }
+}
@@ -0,0 +1,885 @@
+use crate::{
+    client::{
+        create_keystore, AnvilConfig, ClientError, EtherClient, FeePreview, SignerSource,
+        Transport, TxKind, DEFAULT_DERIVATION_PATH,
+    },
+    contract::{Contracts, StorageLayout, StorageReader},
+    utils::prompt_secret,
+};
+use addressbook::{check_checksum, AddressBook, DEFAULT_ADDRESS_BOOK};
+use ethers::{
+    abi::{Param, ParamType},
+    providers::{Provider, StreamExt},
+    signers::Signer,
+    types::{transaction::eip2718::TypedTransaction, Address, BlockId, Filter, TxHash, H256, U256},
+    utils::{format_ether, format_units},
+};
+use serde::Serialize;
+use serde_json::json;
+use std::{
+    collections::{BTreeSet, HashMap},
+    error::Error,
+    io::{self, Write},
+    path::Path,
+};
+
+mod addressbook;
+mod anvil;
+mod deploy;
+mod logs;
+mod multicall;
+mod signature;
+mod simulate;
+mod token;
+mod txpool;
+
+const USAGE: &str = "Usage: rs-ethers [--rpc <url>] <command> [args...]
+
+Without --rpc (or ETH_RPC_URL) commands run against the Anvil node started by
+anvil-start, or else against a throwaway Anvil node spawned for the command.
+
+Commands:
+    block [number|latest]          block header with transaction hashes
+    block-number                   latest block number
+    chain-id                       chain id of the endpoint
+    balance <address>              balance in wei and ether
+    code <address>                 deployed bytecode
+    storage <address> <slot>       raw storage slot, slot as number or 0x hash
+    storage-layout <address> <layout>
+                                   decode state variables, see below
+    proxy <address>                EIP-1967 implementation, admin and beacon
+    tx <hash>                      transaction by hash
+    txpool                         summary of the node's transaction pool, see below
+    gas-price                      gas price in wei and gwei from the provider oracle
+    send <to> <amount> [unit]      send ether, amount may be decimal (0.25), unit
+                                   defaults to ether; shows a fee preview and asks
+                                   for confirmation unless --yes is given
+    estimate <to> <amount> [unit]  fee preview of a transfer without sending it
+    simulate <to> <amount> [unit]  run a transfer or contract call (same arguments
+                                   as send) without sending it, see below
+    call <address> <function> [args...]
+                                   eth_call a contract function and decode the result
+    send <address> <function> [args...]
+                                   sign and send a contract call, --value <ether>
+                                   attaches ether; estimate takes the same form
+    sign <message>                 EIP-191 personal message signature, see below
+    recover <message> <signature>  address that signed message
+    verify <message> <signature> <address>
+                                   check that address signed message
+    watch-blocks [count]           stream new block headers
+    watch-pending [count]          stream pending transaction hashes
+    watch-logs <address> [topic0] [count]
+                                   stream logs emitted by a contract
+    logs <address> [event]         decoded eth_getLogs of a contract, see below
+    multicall <file>               batched read-only calls from a JSON file, see below
+    deploy <source> [args...]      compile a .sol file or read a Foundry/Hardhat
+                                   artifact, deploy it with the constructor args
+                                   and write the deployment to a JSON file
+    token-info <token>             ERC-20 name, symbol, decimals and total supply
+    token-balance <token> <holder...>
+                                   balances in token units and raw
+    token-allowance <token> <owner> <spender>
+                                   remaining allowance of spender
+    token-transfer <token> <to> <amount>
+                                   transfer a decimal amount in token units
+    token-approve <token> <spender> <amount|max>
+                                   approve spender, max for unlimited
+    nft-owner <collection> <id>    ERC-721 owner of a token id
+    nft-uri <collection> <id>      ERC-721 tokenURI with collection name and symbol
+    address                        address of the loaded signer
+    label <label> <address>        save an address book label for the endpoint's chain
+    unlabel <label>                remove a label
+    labels                         labels of the endpoint's chain
+    resolve <name>                 ENS name to address
+    lookup <address>               ENS reverse record and address book labels
+    anvil-start                    start a persistent Anvil node, see below
+    anvil-stop                     stop it, writing --state when it was given
+    anvil-status                   whether it runs, with its endpoint and settings
+    snapshot                       evm_snapshot id of the current chain state
+    revert <snapshot>              evm_revert to a snapshot, usable once
+    increase-time <seconds>        evm_increaseTime, then mines a block to apply it
+                                   unless --no-mine is given
+    mine [blocks]                  evm_mine blocks (default 1)
+    dump-state <file>              anvil_dumpState into a file
+    load-state <file>              anvil_loadState from a file written by dump-state
+    new-keystore <dir> [name]      create an encrypted keystore for a new random key
+    import-keystore <dir> [name]   encrypt an existing private key into a keystore
+
+Contract functions are looked up in --abi <file> (ABI array or Foundry/Hardhat
+artifact) by name or name(types), or given as a full signature without --abi,
+e.g. 'balanceOf(address)(uint256)'. Arguments are parsed by ABI type: arrays as
+[a,b], tuples as (a,b), bytes as hex. call takes --block <number>.
+
+logs searches --from <block> (default 1000 blocks back) to --to <block|latest>
+in requests of --chunk blocks (default 10000), halved whenever the provider
+refuses a range. Logs are decoded with --abi, or with the event given as a
+signature like 'Transfer(address indexed,address indexed,uint256)'. Output is
+one JSON object per line or --format csv; --follow keeps polling for new blocks
+every --interval seconds (default 4).
+
+storage-layout reads solc's storageLayout JSON (alone or inside an artifact)
+or a manual JSON list of label, slot, offset and type entries with Solidity types
+like 'uint128', 'string', 'address[]' or 'mapping(address => uint256)'. Packed
+slots, structs, strings and bytes are decoded. Mappings are read for the keys
+given as --key <label>=<key>, repeatable, nested mapping keys joined by ':'.
+Dynamic arrays show their length and the first --items elements (default 10).
+
+multicall reads a JSON list of calls with address, function, args and abi keys,
+function and abi as for call, and runs them through Multicall3 aggregate3 in
+chunks of --chunk calls (default 500). On Anvil, aggregate3 is installed at
+Multicall3's canonical address when missing. --mode rpc sends the calls as one
+JSON-RPC batch of eth_call instead, for chains without Multicall3. Takes
+--block <number>.
+
+simulate runs the transaction through eth_call and debug_traceCall at --block
+(default latest) and prints the result or decoded revert reason, gas used, the
+call tree, emitted logs (decoded with --abi) and the balance, nonce and storage
+changes. --sender <address> simulates from any account without a signer.
+Nodes without the debug API only give result and gas; --fork runs it on an
+Anvil fork of the --rpc endpoint instead.
+
+txpool counts pending and queued transactions per sender, lists nonce gaps
+that hold queued ones back and the gas price and priority fee distribution.
+--from <address>, --to <address> and --selector <0x1234abcd|signature> filter
+the pool, --raw prints the filtered txpool_content instead. --watch prints one
+JSON line per added, removed, promoted or demoted transaction every
+--interval seconds (default 4).
+
+Wherever an address is expected, including address parameters of contract
+functions and constructors and addresses inside array and tuple arguments, a
+label from the address book or an ENS name ending in .eth may be given
+instead, except for verify, which runs without a node and takes a hex
+address. Labels are kept per chain id in --address-book <file> (default
+addressbook.json); ENS needs a chain with the registry deployed. Mixed-case
+addresses that fail their EIP-55 checksum are used with a warning.
+
+Anvil nodes, persistent or throwaway, take --port <n> (default 8545 for
+anvil-start), --chain-id <id>, --block-time <seconds> (default: a block per
+transaction), --accounts <n>, --fork-url <url> with --fork-block <number>, and
+--state <file>. anvil-start loads the state file when it exists and writes it
+on anvil-stop, a throwaway node only loads it. Sessions are kept per port in
+~/.rs-ethers/anvil/<port>.json, the node's output in <port>.log. Without
+--port, commands use the first running session.
+
+deploy takes --contract <name> when the source defines several, --value
+<ether>, --yes and --out <file> (default deployments/<chain id>/<name>.json).
+The file holds address, gas used and the compiler version and settings needed
+for source verification.
+
+sign, recover and verify take the message as text, as hex bytes with --hex,
+or with --typed the path of EIP-712 typed data JSON (types, primaryType,
+domain, message). Output has the signed hash and the signature both as 65
+byte r||s||v and as EIP-2098 compact, with r, s, v and y parity split out.
+Signatures are accepted in either form.
+
+send, estimate, deploy, token-transfer and token-approve take --type
+legacy|2930|1559 (default 1559) and --access-list to attach the list returned
+by eth_createAccessList. Fees come from eth_feeHistory over the last blocks.
+
+Signer for the commands that send or sign, first match wins:
+    --keystore <file>              encrypted JSON keystore, the password is read
+                                   from KEYSTORE_PASSWORD or prompted
+    --mnemonic <file|->            BIP39 phrase from a file, - prompts for it
+        --mnemonic-path <path>     derivation prefix, default m/44'/60'/0'/0
+        --mnemonic-index <n>       account index appended to the path, default 0
+    the first Anvil dev account when no endpoint is given
+    SIGNER_PRIVATE_KEY             raw hex key from the environment or .env
+
+The endpoint may be http(s)://, ws(s):// or an IPC socket path. The watch
+commands need a ws:// or IPC endpoint and print one JSON object per line,
+stopping after count items when given.
+
+Output is JSON on stdout, errors go to stderr with a non-zero exit code.";
+
+/// Contract bound to the endpoint, as used for calls and decoding.
+type CliContract = Contracts<Provider<Transport>>;
+
+/// Options that take a value, every other `--name` is a flag.
+const VALUE_OPTIONS: [&str; 28] = [
+    "--contract",
+    "--out",
+    "--type",
+    "--abi",
+    "--value",
+    "--block",
+    "--from",
+    "--to",
+    "--sender",
+    "--selector",
+    "--chunk",
+    "--format",
+    "--mode",
+    "--interval",
+    "--key",
+    "--items",
+    "--keystore",
+    "--mnemonic",
+    "--mnemonic-path",
+    "--mnemonic-index",
+    "--port",
+    "--chain-id",
+    "--block-time",
+    "--accounts",
+    "--fork-url",
+    "--fork-block",
+    "--state",
+    "--address-book",
+];
+
+pub struct Cli {
+    pub rpc: Option<String>,
+    pub command: String,
+    pub args: Vec<String>,
+    pub options: Vec<(String, Option<String>)>,
+    /// Address book labels and ENS names found by `resolve_names`.
+    names: HashMap<String, Address>,
+}
+
+impl Cli {
+    /// Parses the process arguments, `None` means usage was printed.
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Option<Cli>, Box<dyn Error>> {
+        let mut rpc = std::env::var("ETH_RPC_URL").ok();
+        let mut rest = Vec::new();
+        let mut options = Vec::new();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--rpc" => rpc = Some(args.next().ok_or("--rpc needs a url")?),
+                "-h" | "--help" | "help" => {
+                    println!("{USAGE}");
+                    return Ok(None);
+                }
+                option if VALUE_OPTIONS.contains(&option) => {
+                    let value = args.next().ok_or(format!("{option} needs a value"))?;
+                    options.push((arg, Some(value)));
+                }
+                option if option.starts_with("--") => options.push((arg, None)),
+                _ => rest.push(arg),
+            }
+        }
+
+        if rest.is_empty() {
+            println!("{USAGE}");
+            return Ok(None);
+        }
+        let command = rest.remove(0);
+
+        Ok(Some(Cli {
+            rpc,
+            command,
+            args: rest,
+            options,
+            names: HashMap::new(),
+        }))
+    }
+
+    pub fn needs_signer(&self) -> bool {
+        match self.command.as_str() {
+            "send" | "estimate" | "deploy" | "sign" | "address" | "token-transfer"
+            | "token-approve" => true,
+            // Simulations only need a sender, the signer's address unless --sender is given.
+            "simulate" => self.option("--sender").is_none(),
+            _ => false,
+        }
+    }
+
+    /// Commands that only touch local files or data and need no node.
+    pub fn is_local(&self) -> bool {
+        matches!(
+            self.command.as_str(),
+            "new-keystore"
+                | "import-keystore"
+                | "recover"
+                | "verify"
+                | "anvil-start"
+                | "anvil-stop"
+                | "anvil-status"
+        )
+    }
+
+    /// Settings for the Anvil node spawned when no endpoint is given.
+    pub fn anvil_config(&self) -> Result<AnvilConfig, Box<dyn Error>> {
+        Ok(AnvilConfig {
+            port: self.option("--port").map(str::parse).transpose()?,
+            chain_id: self.option("--chain-id").map(str::parse).transpose()?,
+            block_time: self.option("--block-time").map(str::parse).transpose()?,
+            accounts: self.option("--accounts").map(str::parse).transpose()?,
+            fork_url: self.option("--fork-url").map(str::to_string),
+            fork_block: self.option("--fork-block").map(str::parse).transpose()?,
+            state: self.option("--state").map(Into::into),
+        })
+    }
+
+    pub fn signer_source(&self, has_anvil: bool) -> Result<SignerSource, Box<dyn Error>> {
+        if let Some(path) = self.option("--keystore") {
+            let password = match std::env::var("KEYSTORE_PASSWORD") {
+                Ok(password) => password,
+                Err(_) => prompt_secret(&format!("Password for {path}"))?,
+            };
+            return Ok(SignerSource::Keystore {
+                path: path.into(),
+                password,
+            });
+        }
+
+        if let Some(phrase) = self.option("--mnemonic") {
+            let phrase = match phrase {
+                "-" => prompt_secret("Mnemonic phrase")?,
+                file => std::fs::read_to_string(file)?,
+            };
+            let index = match self.option("--mnemonic-index") {
+                Some(index) => index.parse()?,
+                None => 0,
+            };
+            return Ok(SignerSource::Mnemonic {
+                phrase,
+                path: self
+                    .option("--mnemonic-path")
+                    .unwrap_or(DEFAULT_DERIVATION_PATH)
+                    .to_string(),
+                index,
+            });
+        }
+
+        if has_anvil {
+            return Ok(SignerSource::Anvil);
+        }
+
+        if let Ok(key) = std::env::var("SIGNER_PRIVATE_KEY") {
+            return Ok(SignerSource::PrivateKey(key));
+        }
+        if let Ok(key) = std::env::var("SIGNER_PRIVET_KEY") {
+            eprintln!("Warning: SIGNER_PRIVET_KEY is deprecated, rename it to SIGNER_PRIVATE_KEY or move the key into a keystore");
+            return Ok(SignerSource::PrivateKey(key));
+        }
+
+        Err(ClientError::NoSigner.into())
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.options.iter().any(|(option, _)| option == name)
+    }
+
+    fn option(&self, name: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .find(|(option, _)| option == name)
+            .and_then(|(_, value)| value.as_deref())
+    }
+
+    /// Every value of an option that may be repeated.
+    fn options(&self, name: &str) -> Vec<&str> {
+        self.options
+            .iter()
+            .filter(|(option, _)| option == name)
+            .filter_map(|(_, value)| value.as_deref())
+            .collect()
+    }
+
+    pub fn needs_pubsub(&self) -> bool {
+        self.command.starts_with("watch-")
+    }
+
+    /// Optional trailing item limit of the watch commands.
+    fn count(&self, index: usize) -> Result<Option<usize>, Box<dyn Error>> {
+        match self.args.get(index) {
+            Some(count) => Ok(Some(count.parse()?)),
+            None => Ok(None),
+        }
+    }
+
+    fn arg(&self, index: usize, name: &str) -> Result<&str, Box<dyn Error>> {
+        match self.args.get(index) {
+            Some(arg) => Ok(arg),
+            None => Err(format!("{} needs <{}>, see --help", self.command, name).into()),
+        }
+    }
+
+    /// Loads the address book labels of the endpoint's chain and resolves the
+    /// ENS names among the arguments, for `address` to look up.
+    pub async fn resolve_names(&mut self, client: &EtherClient) -> Result<(), Box<dyn Error>> {
+        let path = self
+            .option("--address-book")
+            .unwrap_or(DEFAULT_ADDRESS_BOOK);
+        if Path::new(path).exists() {
+            let chain_id = client.get_chain_id().await?.as_u64();
+            self.names = AddressBook::load(path)?
+                .labels(chain_id)
+                .into_iter()
+                .collect();
+        }
+
+        let values = self
+            .args
+            .iter()
+            .chain(self.options.iter().filter_map(|(_, value)| value.as_ref()));
+        // A name given several times is looked up once.
+        let ens_names: BTreeSet<String> = values
+            .filter(|value| value.ends_with(".eth") && !self.names.contains_key(*value))
+            .cloned()
+            .collect();
+        // Not every such argument is an address, a name that fails is left for
+        // `address` to report if it is used as one.
+        for name in ens_names {
+            if let Ok(address) = client.resolve_name(&name).await {
+                self.names.insert(name, address);
+            }
+        }
+        Ok(())
+    }
+
+    /// Hex address, address book label or ENS name resolved by `resolve_names`.
+    fn address(&self, value: &str) -> Result<Address, ClientError> {
+        if let Some(address) = self.names.get(value) {
+            return Ok(*address);
+        }
+        let address: Address = value
+            .parse()
+            .map_err(|_| ClientError::InvalidAddress(value.to_string()))?;
+        check_checksum(value, &address);
+        Ok(address)
+    }
+
+    /// `args` with the labels and ENS names given for address parameters replaced by hex.
+    fn resolve_args(&self, inputs: &[Param], args: &[String]) -> Result<Vec<String>, ClientError> {
+        args.iter()
+            .enumerate()
+            .map(|(index, arg)| match inputs.get(index) {
+                Some(param) => self.resolve_value(&param.kind, arg),
+                None => Ok(arg.clone()),
+            })
+            .collect()
+    }
+
+    /// Resolves addresses nested in `[..]` arrays and `(..)` tuples too. Values
+    /// that do not have the shape of their type are left for the tokenizer to reject.
+    fn resolve_value(&self, kind: &ParamType, value: &str) -> Result<String, ClientError> {
+        match kind {
+            ParamType::Address => Ok(format!("{:?}", self.address(value.trim())?)),
+            ParamType::Array(inner) | ParamType::FixedArray(inner, _) => {
+                let Some(items) = split_list(value, '[', ']') else {
+                    return Ok(value.to_string());
+                };
+                let items = items
+                    .into_iter()
+                    .map(|item| self.resolve_value(inner, item))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(format!("[{}]", items.join(",")))
+            }
+            ParamType::Tuple(kinds) => match split_list(value, '(', ')') {
+                Some(items) if items.len() == kinds.len() => {
+                    let items = kinds
+                        .iter()
+                        .zip(items)
+                        .map(|(kind, item)| self.resolve_value(kind, item))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    Ok(format!("({})", items.join(",")))
+                }
+                _ => Ok(value.to_string()),
+            },
+            _ => Ok(value.to_string()),
+        }
+    }
+}
+
+/// Top level items of `value` between `open` and `close`, `None` when it is not
+/// such a list. Commas inside nested brackets or double-quoted strings do not split.
+fn split_list(value: &str, open: char, close: char) -> Option<Vec<&str>> {
+    let inner = value.trim().strip_prefix(open)?.strip_suffix(close)?;
+    if inner.trim().is_empty() {
+        return Some(Vec::new());
+    }
+
+    let mut items = Vec::new();
+    let mut depth = 0;
+    let mut quoted = false;
+    let mut start = 0;
+    for (index, c) in inner.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '[' | '(' if !quoted => depth += 1,
+            ']' | ')' if !quoted => depth -= 1,
+            ',' if !quoted && depth == 0 => {
+                items.push(inner[start..index].trim());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(inner[start..].trim());
+    Some(items)
+}
+
+pub async fn run(cli: &Cli, client: &EtherClient) -> Result<(), Box<dyn Error>> {
+    match cli.command.as_str() {
+        "block" => {
+            let number = match cli.args.first().map(String::as_str) {
+                None | Some("latest") => client.get_block_count().await?.as_u64(),
+                Some(number) => number.parse()?,
+            };
+            match client.get_block(number).await? {
+                Some(block) => print_json(&block),
+                None => Err(ClientError::BlockNotFound(number).into()),
+            }
+        }
+        "block-number" => print_json(&client.get_block_count().await?.as_u64()),
+        "chain-id" => print_json(&client.get_chain_id().await?.as_u64()),
+        "balance" => {
+            let address = cli.address(cli.arg(0, "address")?)?;
+            let balance = client.get_account_balance(address).await?;
+            print_json(&json!({
+                "address": address,
+                "wei": balance.to_string(),
+                "ether": format_ether(balance),
+            }))
+        }
+        "code" => {
+            let address = cli.address(cli.arg(0, "address")?)?;
+            let code = client.get_code(address).await?;
+            print_json(&json!({
+                "address": address,
+                "is_contract": !code.is_empty(),
+                "code": code,
+            }))
+        }
+        "storage" => {
+            let address = cli.address(cli.arg(0, "address")?)?;
+            let slot = parse_slot(cli.arg(1, "slot")?)?;
+            print_json(&client.get_slot_data(address, slot).await?)
+        }
+        "tx" => {
+            let hash: TxHash = cli.arg(0, "hash")?.parse()?;
+            match client.get_transaction_data(hash).await? {
+                Some(tx) => print_json(&tx),
+                None => Err(format!("transaction {hash:?} not found").into()),
+            }
+        }
+        "txpool" => txpool::run(cli, client).await,
+        "gas-price" => {
+            let price = client.get_gas_price_oracle().await?;
+            print_json(&json!({
+                "wei": price.to_string(),
+                "gwei": format_units(price, "gwei")?,
+            }))
+        }
+        "send" => {
+            let (tx, preview) = prepare_transfer(cli, client).await?;
+
+            eprintln!("{}", serde_json::to_string_pretty(&preview)?);
+            if !cli.flag("--yes") && !confirm("Sign and send this transaction?")? {
+                return Err("aborted".into());
+            }
+            print_json(&client.send_raw_tx(tx).await?)
+        }
+        "call" => {
+            let contract = contract(cli, client)?;
+            let function = contract.function(cli.arg(1, "function")?)?;
+            let block = match cli.option("--block") {
+                Some(number) => Some(BlockId::from(number.parse::<u64>()?)),
+                None => None,
+            };
+
+            let args = cli.resolve_args(&function.inputs, &cli.args[2..])?;
+            let result = contract.call(function, &args, block).await?;
+            print_json(&result)
+        }
+        "storage-layout" => {
+            let layout = StorageLayout::load(cli.arg(1, "layout")?)?;
+            let reader = storage_reader(cli, client)?;
+            print_json(&reader.read_layout(&layout).await?)
+        }
+        "proxy" => {
+            let reader = storage_reader(cli, client)?;
+            print_json(&reader.read_proxy().await?)
+        }
+        "logs" => logs::run(cli, client).await,
+        "simulate" => simulate::run(cli, client).await,
+        "multicall" => multicall::run(cli, client).await,
+        "token-info" | "token-balance" | "token-allowance" | "token-transfer" | "token-approve"
+        | "nft-owner" | "nft-uri" => token::run(cli, client).await,
+        "deploy" => deploy::run(cli, client).await,
+        "estimate" => {
+            let (_, preview) = prepare_transfer(cli, client).await?;
+            print_json(&preview)
+        }
+        "address" => {
+            let wallet = client.get_wallet().ok_or(ClientError::NoSigner)?;
+            print_json(&wallet.address())
+        }
+        "sign" => signature::sign(cli, client).await,
+        "label" | "unlabel" | "labels" | "resolve" | "lookup" => {
+            addressbook::run(cli, client).await
+        }
+        "snapshot" | "revert" | "increase-time" | "mine" | "dump-state" | "load-state" => {
+            anvil::run(cli, client).await
+        }
+        "watch-blocks" => {
+            let stream = client.subscribe_new_heads().await?;
+            print_stream(stream, cli.count(0)?).await
+        }
+        "watch-pending" => {
+            let stream = client.subscribe_pending_txs().await?;
+            print_stream(stream, cli.count(0)?).await
+        }
+        "watch-logs" => {
+            let address = cli.address(cli.arg(0, "address")?)?;
+            let mut filter = Filter::new().address(address);
+            let mut count_index = 1;
+            if let Some(topic) = cli.args.get(1).filter(|topic| topic.starts_with("0x")) {
+                filter = filter.topic0(topic.parse::<H256>()?);
+                count_index = 2;
+            }
+
+            let stream = client.subscribe_logs(&filter).await?;
+            print_stream(stream, cli.count(count_index)?).await
+        }
+        command => Err(format!("unknown command {command}, see --help").into()),
+    }
+}
+
+pub fn run_local(cli: &Cli) -> Result<(), Box<dyn Error>> {
+    match cli.command.as_str() {
+        "new-keystore" | "import-keystore" => {
+            let dir = cli.arg(0, "dir")?;
+            let name = cli.args.get(1).map(String::as_str);
+
+            let private_key = match cli.command.as_str() {
+                "import-keystore" => Some(prompt_secret("Private key")?),
+                _ => None,
+            };
+            let password = prompt_secret("New keystore password")?;
+            if password != prompt_secret("Repeat password")? {
+                return Err("passwords do not match".into());
+            }
+
+            let (wallet, path) =
+                create_keystore(Path::new(dir), &password, private_key.as_deref(), name)?;
+            print_json(&json!({
+                "address": wallet.address(),
+                "keystore": path,
+            }))
+        }
+        "recover" | "verify" => signature::check(cli),
+        "anvil-start" | "anvil-stop" | "anvil-status" => anvil::run_local(cli),
+        command => Err(format!("unknown command {command}, see --help").into()),
+    }
+}
+
+/// Builds the transfer given by `<to> <amount> [unit]` with gas and fees filled in.
+async fn prepare_transfer(
+    cli: &Cli,
+    client: &EtherClient,
+) -> Result<(TypedTransaction, FeePreview), Box<dyn Error>> {
+    let (mut tx, contract) = build_tx(cli, client)?;
+
+    match client.prepare_tx(&mut tx, cli.flag("--access-list")).await {
+        Ok(preview) => Ok((tx, preview)),
+        Err(err) => match (err.revert_data(), contract) {
+            (Some(data), Some(contract)) => {
+                Err(format!("reverted: {}", contract.decode_revert(&data)).into())
+            }
+            _ => Err(err.into()),
+        },
+    }
+}
+
+/// Transfer `<to> <amount> [unit]` or contract call `<address> <function> [args...]`,
+/// with the contract when it is a call.
+fn build_tx(
+    cli: &Cli,
+    client: &EtherClient,
+) -> Result<(TypedTransaction, Option<CliContract>), Box<dyn Error>> {
+    let kind: TxKind = cli.option("--type").unwrap_or("1559").parse()?;
+
+    // A second argument that is not a number names a contract function.
+    let is_call = cli.option("--abi").is_some()
+        || cli
+            .args
+            .get(1)
+            .is_some_and(|arg| arg.parse::<f64>().is_err());
+
+    Ok(match is_call {
+        true => {
+            let contract = contract(cli, client)?;
+            let function = contract.function(cli.arg(1, "function")?)?;
+            let args = cli.resolve_args(&function.inputs, &cli.args[2..])?;
+            let request = contract.transaction(function, &args)?;
+            let value = cli.option("--value").unwrap_or("0");
+            (
+                client.create_contract_tx(request, value, kind)?,
+                Some(contract),
+            )
+        }
+        false => {
+            let to = cli.address(cli.arg(0, "to")?)?;
+            let amount = cli.arg(1, "amount")?;
+            let unit = cli.args.get(2).map_or("ether", String::as_str);
+            (client.create_raw_coin_tx(to, amount, unit, kind)?, None)
+        }
+    })
+}
+
+/// Dynamic array elements read by storage-layout when --items is not given.
+const DEFAULT_STORAGE_ITEMS: usize = 10;
+
+fn storage_reader<'a>(
+    cli: &Cli,
+    client: &'a EtherClient,
+) -> Result<StorageReader<'a>, Box<dyn Error>> {
+    let mut keys: HashMap<String, Vec<String>> = HashMap::new();
+    for key in cli.options("--key") {
+        let (label, key) = key
+            .split_once('=')
+            .ok_or(format!("--key {key} is not <label>=<key>"))?;
+        keys.entry(label.to_string())
+            .or_default()
+            .push(key.to_string());
+    }
+
+    Ok(StorageReader {
+        client,
+        address: cli.address(cli.arg(0, "address")?)?,
+        keys,
+        max_items: match cli.option("--items") {
+            Some(items) => items.parse()?,
+            None => DEFAULT_STORAGE_ITEMS,
+        },
+    })
+}
+
+/// Contract named by `<address> <function>`, with the ABI from --abi or the signature.
+fn contract(cli: &Cli, client: &EtherClient) -> Result<CliContract, Box<dyn Error>> {
+    let address = format!("{:?}", cli.address(cli.arg(0, "address")?)?);
+    let address = address.as_str();
+    let function = cli.arg(1, "function")?;
+
+    let contract = match cli.option("--abi") {
+        Some(path) => Contracts::new(client.get_provider(), address, path)?,
+        None if function.contains('(') => {
+            Contracts::from_signature(client.get_provider(), address, function)?
+        }
+        None => return Err(format!("pass --abi <file> or a full signature for {function}").into()),
+    };
+    Ok(contract)
+}
+
+fn confirm(question: &str) -> Result<bool, Box<dyn Error>> {
+    eprint!("{question} [y/N] ");
+    io::stderr().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+fn print_json<T: Serialize>(value: &T) -> Result<(), Box<dyn Error>> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// Prints every item as a single JSON line until the stream ends or `limit` is hit.
+async fn print_stream<S>(stream: S, limit: Option<usize>) -> Result<(), Box<dyn Error>>
+where
+    S: StreamExt + Unpin,
+    S::Item: Serialize,
+{
+    let mut stream = stream.take(limit.unwrap_or(usize::MAX));
+    while let Some(item) = stream.next().await {
+        println!("{}", serde_json::to_string(&item)?);
+    }
+    Ok(())
+}
+
+/// Slots are given either as a decimal index or as a full 32 byte hash.
+fn parse_slot(slot: &str) -> Result<H256, Box<dyn Error>> {
+    if slot.starts_with("0x") && slot.len() == 66 {
+        return Ok(slot.parse()?);
+    }
+
+    let index = match slot.strip_prefix("0x") {
+        Some(hex) => U256::from_str_radix(hex, 16)?,
+        None => U256::from_dec_str(slot)?,
+    };
+    let mut bytes = [0u8; 32];
+    index.to_big_endian(&mut bytes);
+    Ok(H256::from(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VAULT: &str = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+
+    fn cli() -> Cli {
+        Cli {
+            rpc: None,
+            command: "call".to_string(),
+            args: Vec::new(),
+            options: Vec::new(),
+            names: HashMap::from([("vault".to_string(), VAULT.parse().unwrap())]),
+        }
+    }
+
+    #[test]
+    fn splits_top_level_items() {
+        let cases: [(&str, char, char, Option<Vec<&str>>); 7] = [
+            ("[]", '[', ']', Some(vec![])),
+            (" [ a , b ] ", '[', ']', Some(vec!["a", "b"])),
+            ("[[a,b],[c]]", '[', ']', Some(vec!["[a,b]", "[c]"])),
+            (
+                "(a,(b,c),[d,e])",
+                '(',
+                ')',
+                Some(vec!["a", "(b,c)", "[d,e]"]),
+            ),
+            (r#"["a,b",c]"#, '[', ']', Some(vec![r#""a,b""#, "c"])),
+            ("a,b", '[', ']', None),
+            ("[a,b)", '[', ']', None),
+        ];
+
+        for (value, open, close, expected) in cases {
+            assert_eq!(split_list(value, open, close), expected, "{value}");
+        }
+    }
+
+    #[test]
+    fn resolves_addresses_in_arguments() {
+        let cli = cli();
+        let vault = format!("{:?}", VAULT.parse::<Address>().unwrap());
+        let address = ParamType::Address;
+        let addresses = ParamType::Array(Box::new(ParamType::Address));
+        let tuple = ParamType::Tuple(vec![ParamType::Uint(256), addresses.clone()]);
+
+        let cases = [
+            (&address, "vault", vault.clone()),
+            (&address, " vault ", vault.clone()),
+            (&address, VAULT, vault.clone()),
+            (&address, &VAULT.to_lowercase(), vault.clone()),
+            // A checksum mismatch only warns.
+            (
+                &address,
+                "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD",
+                vault.clone(),
+            ),
+            (&addresses, "[vault, vault]", format!("[{vault},{vault}]")),
+            (&addresses, "[]", "[]".to_string()),
+            (&tuple, "(1,[vault])", format!("(1,[{vault}])")),
+            // Values without the shape of their type are left to the tokenizer.
+            (&tuple, "(1)", "(1)".to_string()),
+            (&ParamType::Uint(256), "vault", "vault".to_string()),
+        ];
+
+        for (kind, value, expected) in cases {
+            assert_eq!(cli.resolve_value(kind, value).unwrap(), expected, "{value}");
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_labels() {
+        let cli = cli();
+        let addresses = ParamType::Array(Box::new(ParamType::Address));
+        assert!(cli.resolve_value(&ParamType::Address, "treasury").is_err());
+        assert!(cli.resolve_value(&addresses, "[vault,treasury]").is_err());
+    }
+}
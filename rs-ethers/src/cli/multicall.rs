@@ -0,0 +1,139 @@
+use super::{print_json, Cli, CliContract};
+use crate::{
+    client::{CallOutput, EtherClient},
+    contract::{outputs_to_json, Contracts},
+};
+use ethers::{
+    abi::Function,
+    types::{Address, BlockId, Bytes},
+    utils::hex,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{error::Error, fs};
+
+/// Calls per `aggregate3` or JSON-RPC batch when --chunk is not given.
+const DEFAULT_BATCH_SIZE: usize = 500;
+
+/// One entry of the calls file.
+#[derive(Deserialize)]
+struct CallSpec {
+    address: String,
+    /// Name with `abi`, or a full signature like `balanceOf(address)(uint256)`.
+    function: String,
+    #[serde(default)]
+    args: Vec<Value>,
+    abi: Option<String>,
+}
+
+struct Call {
+    contract: CliContract,
+    function: Function,
+    target: Address,
+    data: Bytes,
+}
+
+/// `multicall <file>`: read-only calls from a JSON file, batched.
+pub async fn run(cli: &Cli, client: &EtherClient) -> Result<(), Box<dyn Error>> {
+    let path = cli.arg(0, "file")?;
+    let specs: Vec<CallSpec> = serde_json::from_str(&fs::read_to_string(path)?)?;
+    let calls = specs
+        .iter()
+        .enumerate()
+        .map(|(index, spec)| {
+            call(cli, client, spec).map_err(|err| format!("call {index} in {path}: {err}").into())
+        })
+        .collect::<Result<Vec<Call>, Box<dyn Error>>>()?;
+
+    let rpc_batch = match cli.option("--mode").unwrap_or("multicall") {
+        "multicall" => false,
+        "rpc" => true,
+        mode => return Err(format!("unknown mode {mode}, use multicall or rpc").into()),
+    };
+    let size = match cli.option("--chunk") {
+        Some(size) => size.parse()?,
+        None => DEFAULT_BATCH_SIZE,
+    };
+    let block = match cli.option("--block") {
+        Some(number) => Some(BlockId::from(number.parse::<u64>()?)),
+        None => None,
+    };
+
+    let mut results = Vec::with_capacity(calls.len());
+    for chunk in calls.chunks(size.max(1)) {
+        let requests: Vec<(Address, Bytes)> = chunk
+            .iter()
+            .map(|call| (call.target, call.data.clone()))
+            .collect();
+        let outputs = match rpc_batch {
+            true => client.batch_call(&requests, block).await?,
+            false => client.multicall(&requests, block).await?,
+        };
+        results.extend(
+            chunk
+                .iter()
+                .zip(outputs)
+                .map(|(call, output)| decode(call, output)),
+        );
+    }
+
+    print_json(&results)
+}
+
+fn call(cli: &Cli, client: &EtherClient, spec: &CallSpec) -> Result<Call, Box<dyn Error>> {
+    let target = cli.address(&spec.address)?;
+    let address = format!("{target:?}");
+    let contract = match &spec.abi {
+        Some(abi) => Contracts::new(client.get_provider(), &address, abi)?,
+        None => Contracts::from_signature(client.get_provider(), &address, &spec.function)?,
+    };
+    let function = contract.function(&spec.function)?.clone();
+
+    // Arguments may be written as JSON values, the tokenizer wants their text.
+    let args: Vec<String> = spec
+        .args
+        .iter()
+        .map(|arg| match arg {
+            Value::String(text) => text.clone(),
+            value => value.to_string(),
+        })
+        .collect();
+    let args = cli.resolve_args(&function.inputs, &args)?;
+    let data = contract.encode(&function, &args)?;
+
+    Ok(Call {
+        target,
+        contract,
+        function,
+        data,
+    })
+}
+
+fn decode(call: &Call, output: CallOutput) -> Value {
+    let mut result = json!({
+        "address": call.target,
+        "function": call.function.name,
+    });
+
+    match output {
+        Ok(data) => match call.function.decode_output(&data) {
+            Ok(tokens) => {
+                result["success"] = json!(true);
+                result["result"] = outputs_to_json(&call.function, tokens);
+            }
+            // Calls to accounts without code succeed with empty output.
+            Err(_) => {
+                result["success"] = json!(false);
+                result["error"] = json!(format!(
+                    "undecodable output {}",
+                    hex::encode_prefixed(&data)
+                ));
+            }
+        },
+        Err(data) => {
+            result["success"] = json!(false);
+            result["revert_reason"] = json!(call.contract.decode_revert(&data));
+        }
+    }
+    result
+}
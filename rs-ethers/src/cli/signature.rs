@@ -0,0 +1,265 @@
+use super::{print_json, Cli};
+use crate::client::{ClientError, EtherClient};
+use ethers::{
+    signers::Signer,
+    types::{
+        transaction::eip712::{Eip712, TypedData},
+        Address, Signature, U256,
+    },
+    utils::{hash_message, hex},
+};
+use serde_json::{json, Value};
+use std::{error::Error, fs};
+
+/// What gets signed: an EIP-191 personal message or EIP-712 typed data from a JSON file.
+enum Payload {
+    Message(Vec<u8>),
+    Typed(Box<TypedData>),
+}
+
+impl Payload {
+    /// `<message>` as text, as bytes with --hex, or the typed data file with --typed.
+    fn parse(cli: &Cli) -> Result<Payload, Box<dyn Error>> {
+        let input = cli.arg(0, "message")?;
+
+        if cli.flag("--typed") {
+            let data: TypedData = serde_json::from_str(&fs::read_to_string(input)?)?;
+            return Ok(Payload::Typed(Box::new(data)));
+        }
+        match cli.flag("--hex") {
+            true => Ok(Payload::Message(hex::decode(input)?)),
+            false => Ok(Payload::Message(input.as_bytes().to_vec())),
+        }
+    }
+
+    /// Digest the signature commits to.
+    fn hash(&self) -> Result<[u8; 32], Box<dyn Error>> {
+        match self {
+            Payload::Message(message) => Ok(hash_message(message).0),
+            Payload::Typed(data) => Ok(data.encode_eip712()?),
+        }
+    }
+
+    fn recover(&self, signature: &Signature) -> Result<Address, Box<dyn Error>> {
+        match self {
+            Payload::Message(message) => Ok(signature.recover(message.as_slice())?),
+            Payload::Typed(data) => Ok(signature.recover_typed_data(data.as_ref())?),
+        }
+    }
+
+    fn describe(&self) -> Value {
+        match self {
+            Payload::Message(message) => match std::str::from_utf8(message) {
+                Ok(text) => json!({ "message": text }),
+                Err(_) => json!({ "message": hex::encode_prefixed(message) }),
+            },
+            Payload::Typed(data) => json!({
+                "primary_type": data.primary_type,
+                "domain": data.domain,
+            }),
+        }
+    }
+}
+
+/// `sign <message>` with the loaded signer.
+pub async fn sign(cli: &Cli, client: &EtherClient) -> Result<(), Box<dyn Error>> {
+    let payload = Payload::parse(cli)?;
+    let wallet = client.get_wallet().ok_or(ClientError::NoSigner)?;
+
+    let mut output = payload.describe();
+    output["hash"] = json!(hex::encode_prefixed(payload.hash()?));
+    output["address"] = json!(wallet.address());
+    let signature = match &payload {
+        Payload::Message(message) => client.sign_message(message, wallet).await?,
+        Payload::Typed(data) => client.sign_typed_data(data, wallet).await?,
+    };
+    output["signature"] = signature_json(&signature);
+
+    print_json(&output)
+}
+
+/// `recover <message> <signature>` and `verify <message> <signature> <address>`, no node needed.
+pub fn check(cli: &Cli) -> Result<(), Box<dyn Error>> {
+    let payload = Payload::parse(cli)?;
+    let signature = parse_signature(cli.arg(1, "signature")?)?;
+    let recovered = payload.recover(&signature)?;
+
+    let mut output = payload.describe();
+    output["hash"] = json!(hex::encode_prefixed(payload.hash()?));
+    output["signature"] = signature_json(&signature);
+    match cli.command.as_str() {
+        "verify" => {
+            let expected = cli.address(cli.arg(2, "address")?)?;
+            output["address"] = json!(expected);
+            output["recovered"] = json!(recovered);
+            output["valid"] = json!(recovered == expected);
+        }
+        _ => output["address"] = json!(recovered),
+    }
+
+    print_json(&output)
+}
+
+/// 65 byte `r || s || v` hex or the 64 byte EIP-2098 compact form.
+fn parse_signature(signature: &str) -> Result<Signature, Box<dyn Error>> {
+    let bytes = hex::decode(signature)?;
+    match bytes.len() {
+        65 => Ok(Signature::try_from(bytes.as_slice())?),
+        64 => {
+            // The top bit of the second word is the y parity, the rest is s.
+            let y_parity = bytes[32] >> 7;
+            let mut s = bytes[32..].to_vec();
+            s[0] &= 0x7f;
+            Ok(Signature {
+                r: U256::from_big_endian(&bytes[..32]),
+                s: U256::from_big_endian(&s),
+                v: 27 + y_parity as u64,
+            })
+        }
+        length => Err(format!("signature has {length} bytes, expected 65 or 64 (compact)").into()),
+    }
+}
+
+fn signature_json(signature: &Signature) -> Value {
+    let y_parity = match signature.v {
+        // EIP-155 v from a transaction signature.
+        v if v >= 35 => (v - 35) % 2,
+        v if v >= 27 => (v - 27) % 2,
+        v => v % 2,
+    };
+
+    let mut compact = [0u8; 64];
+    signature.r.to_big_endian(&mut compact[..32]);
+    signature.s.to_big_endian(&mut compact[32..]);
+    compact[32] |= (y_parity as u8) << 7;
+
+    let mut r = [0u8; 32];
+    let mut s = [0u8; 32];
+    signature.r.to_big_endian(&mut r);
+    signature.s.to_big_endian(&mut s);
+
+    json!({
+        "signature": hex::encode_prefixed(signature.to_vec()),
+        "compact": hex::encode_prefixed(compact),
+        "r": hex::encode_prefixed(r),
+        "s": hex::encode_prefixed(s),
+        "v": signature.v,
+        "y_parity": y_parity,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::{signers::LocalWallet, types::H256, utils::keccak256};
+
+    /// The Mail example of the EIP-712 specification.
+    const MAIL: &str = r#"{
+        "types": {
+            "EIP712Domain": [
+                { "name": "name", "type": "string" },
+                { "name": "version", "type": "string" },
+                { "name": "chainId", "type": "uint256" },
+                { "name": "verifyingContract", "type": "address" }
+            ],
+            "Person": [
+                { "name": "name", "type": "string" },
+                { "name": "wallet", "type": "address" }
+            ],
+            "Mail": [
+                { "name": "from", "type": "Person" },
+                { "name": "to", "type": "Person" },
+                { "name": "contents", "type": "string" }
+            ]
+        },
+        "primaryType": "Mail",
+        "domain": {
+            "name": "Ether Mail",
+            "version": "1",
+            "chainId": 1,
+            "verifyingContract": "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC"
+        },
+        "message": {
+            "from": { "name": "Cow", "wallet": "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826" },
+            "to": { "name": "Bob", "wallet": "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB" },
+            "contents": "Hello, Bob!"
+        }
+    }"#;
+
+    fn mail() -> Payload {
+        Payload::Typed(Box::new(serde_json::from_str(MAIL).unwrap()))
+    }
+
+    fn wallet() -> LocalWallet {
+        "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318"
+            .parse()
+            .unwrap()
+    }
+
+    fn sign(payload: &Payload) -> Signature {
+        wallet().sign_hash(H256(payload.hash().unwrap())).unwrap()
+    }
+
+    #[test]
+    fn compact_form_round_trips() {
+        for payload in [Payload::Message(b"Some data".to_vec()), mail()] {
+            let signature = sign(&payload);
+            let json = signature_json(&signature);
+            assert_eq!(json["compact"].as_str().unwrap().len(), 2 + 128);
+
+            for form in ["signature", "compact"] {
+                let parsed = parse_signature(json[form].as_str().unwrap()).unwrap();
+                assert_eq!(parsed, signature, "{form}");
+                assert_eq!(payload.recover(&parsed).unwrap(), wallet().address());
+            }
+        }
+        assert!(parse_signature("0x1234").is_err());
+    }
+
+    #[test]
+    fn recovers_personal_message() {
+        let payload = Payload::Message(b"Some data".to_vec());
+        assert_eq!(
+            hex::encode_prefixed(payload.hash().unwrap()),
+            "0x1da44b586eb0729ff70a73c326926f6ed5a25f5b056e7f47fbc6e58d86871655"
+        );
+
+        let signature = parse_signature(
+            "0xb91467e570a6466aa9e9876cbcd013baba02900b8979d43fe208a4a4f339f5fd\
+             6007e74cd82e037b800186422fc2da167c747ef045e5d18a5f5d4300f8e1a0291c",
+        )
+        .unwrap();
+        let expected: Address = "0x2c7536E3605D9C16a7a3D7b1898e529396a65c23"
+            .parse()
+            .unwrap();
+        assert_eq!(payload.recover(&signature).unwrap(), expected);
+        assert_eq!(wallet().address(), expected);
+        assert_eq!(sign(&payload), signature);
+    }
+
+    #[test]
+    fn hashes_and_recovers_typed_data() {
+        let payload = mail();
+        assert_eq!(
+            hex::encode_prefixed(payload.hash().unwrap()),
+            "0xbe609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2"
+        );
+
+        // Signed by keccak256("cow") in the specification.
+        let cow = LocalWallet::from_bytes(&keccak256("cow")).unwrap();
+        let signature = Signature {
+            r: U256::from_str_radix(
+                "4355c47d63924e8a72e509b65029052eb6c299d53a04e167c5775fd466751c9d",
+                16,
+            )
+            .unwrap(),
+            s: U256::from_str_radix(
+                "07299936d304c153f6443dfa05f40ff007d72911b6f72307f996231605b91562",
+                16,
+            )
+            .unwrap(),
+            v: 28,
+        };
+        assert_eq!(payload.recover(&signature).unwrap(), cow.address());
+    }
+}
@@ -0,0 +1,263 @@
+use super::{build_tx, print_json, Cli, CliContract};
+use crate::{
+    client::{AnvilConfig, EtherClient},
+    contract::{decode_revert, input_signature, outputs_to_json},
+};
+use ethers::{
+    abi::{Abi, Function},
+    types::{
+        transaction::eip2718::TypedTransaction, AccountState, BlockId, CallFrame, DiffMode, Log,
+    },
+    utils::{format_ether, hex},
+};
+use serde_json::{json, Map, Value};
+use std::error::Error;
+
+/// `simulate <to> <amount|function> [args...]`: what the transaction would do, without sending it.
+pub async fn run(cli: &Cli, client: &EtherClient) -> Result<(), Box<dyn Error>> {
+    let (mut tx, contract) = build_tx(cli, client)?;
+    if let Some(sender) = cli.option("--sender") {
+        tx.set_from(cli.address(sender)?);
+    }
+    let function = match &contract {
+        Some(contract) => Some(contract.function(cli.arg(1, "function")?)?),
+        None => None,
+    };
+    let block = match cli.option("--block") {
+        Some(number) => Some(number.parse::<u64>()?),
+        None => None,
+    };
+
+    let simulation = Simulation {
+        contract: contract.as_ref(),
+        function,
+    };
+    if !cli.flag("--fork") {
+        let result = simulation
+            .run(client, &tx, block.map(BlockId::from))
+            .await?;
+        return print_json(&result);
+    }
+
+    // The fork serves the debug API even when the upstream endpoint does not.
+    let rpc = cli
+        .rpc
+        .as_deref()
+        .ok_or("--fork needs --rpc <url> to fork from")?;
+    let anvil = AnvilConfig {
+        fork_url: Some(rpc.to_string()),
+        fork_block: block,
+        ..AnvilConfig::default()
+    }
+    .spawn()?;
+    let fork = EtherClient::new(anvil.endpoint()).await?;
+
+    print_json(&simulation.run(&fork, &tx, None).await?)
+}
+
+struct Simulation<'a> {
+    contract: Option<&'a CliContract>,
+    function: Option<&'a Function>,
+}
+
+impl Simulation<'_> {
+    async fn run(
+        &self,
+        client: &EtherClient,
+        tx: &TypedTransaction,
+        block: Option<BlockId>,
+    ) -> Result<Value, Box<dyn Error>> {
+        let (success, result, revert_reason) = match client.call_tx(tx, block).await {
+            Ok(output) => (true, self.decode_output(&output), None),
+            Err(err) => match err.revert_data() {
+                Some(data) => (false, Value::Null, Some(self.decode_revert(&data))),
+                None => return Err(err.into()),
+            },
+        };
+
+        let trace = match client.trace_call(tx, block).await {
+            Ok(frame) => Some(frame),
+            Err(err) => {
+                eprintln!("Warning: debug_traceCall failed ({err}), no call tree, logs or state diff; try --fork");
+                None
+            }
+        };
+        // Some nodes trace calls but not with the prestate tracer, the rest still stands.
+        let state_diff = match trace {
+            Some(_) => match client.trace_state_diff(tx, block).await {
+                Ok(diff) => Some(state_diff(diff)),
+                Err(err) => {
+                    eprintln!("Warning: prestateTracer failed ({err}), no state diff");
+                    None
+                }
+            },
+            None => None,
+        };
+
+        let gas_used = match (&trace, success) {
+            (Some(frame), _) => Some(frame.gas_used),
+            (None, true) => Some(client.estimate_gas(tx, block).await?),
+            (None, false) => None,
+        };
+        let mut logs = Vec::new();
+        if let Some(frame) = &trace {
+            self.collect_logs(frame, &mut logs);
+        }
+
+        Ok(json!({
+            "success": success,
+            "result": result,
+            "revert_reason": revert_reason,
+            "gas_used": gas_used.map(|gas| gas.to_string()),
+            "call_tree": trace.as_ref().map(|frame| self.call_tree(frame, true)),
+            "logs": trace.as_ref().map(|_| logs),
+            "state_diff": state_diff,
+        }))
+    }
+
+    fn decode_output(&self, output: &[u8]) -> Value {
+        match self.function {
+            Some(function) => match function.decode_output(output) {
+                Ok(tokens) => outputs_to_json(function, tokens),
+                Err(_) => json!(hex::encode_prefixed(output)),
+            },
+            None => Value::Null,
+        }
+    }
+
+    fn decode_revert(&self, data: &[u8]) -> String {
+        match self.contract {
+            Some(contract) => contract.decode_revert(data),
+            None => decode_revert(&Abi::default(), data),
+        }
+    }
+
+    /// Frames of the callTracer as JSON, the top frame named after the called function.
+    fn call_tree(&self, frame: &CallFrame, top: bool) -> Value {
+        let mut call = json!({
+            "type": frame.typ,
+            "from": frame.from,
+            "to": frame.to,
+            "value_ether": frame.value.filter(|value| !value.is_zero()).map(format_ether),
+            "gas_used": frame.gas_used.to_string(),
+        });
+
+        let selector = frame.input.get(..4).map(hex::encode_prefixed);
+        match (top, self.function) {
+            (true, Some(function)) => call["function"] = json!(input_signature(function)),
+            _ => call["selector"] = json!(selector),
+        }
+        if let Some(error) = &frame.error {
+            call["error"] = json!(error);
+            if let Some(output) = &frame.output {
+                call["revert_reason"] = json!(self.decode_revert(output));
+            }
+        }
+        if let Some(calls) = &frame.calls {
+            call["calls"] = calls
+                .iter()
+                .map(|frame| self.call_tree(frame, false))
+                .collect();
+        }
+        call
+    }
+
+    /// Logs in emission order, dropping those of frames that reverted.
+    fn collect_logs(&self, frame: &CallFrame, logs: &mut Vec<Value>) {
+        if frame.error.is_some() {
+            return;
+        }
+
+        for log in frame.logs.iter().flatten() {
+            let log = Log {
+                address: log.address.unwrap_or_default(),
+                topics: log.topics.clone().unwrap_or_default(),
+                data: log.data.clone().unwrap_or_default(),
+                ..Default::default()
+            };
+            let decoded = self.contract.and_then(|contract| contract.decode_log(&log));
+            logs.push(match decoded {
+                Some((event, args)) => json!({
+                    "address": log.address,
+                    "event": event.name,
+                    "args": args,
+                }),
+                None => json!({
+                    "address": log.address,
+                    "topics": log.topics,
+                    "data": log.data,
+                }),
+            });
+        }
+        for call in frame.calls.iter().flatten() {
+            self.collect_logs(call, logs);
+        }
+    }
+}
+
+/// Before and after values of every changed balance, nonce, code and storage slot.
+/// The tracer puts only changed fields into `post`, while `pre` has whole accounts.
+fn state_diff(diff: DiffMode) -> Value {
+    let mut accounts = Map::new();
+    let empty = AccountState::default();
+
+    for address in diff.pre.keys().chain(diff.post.keys()) {
+        let key = format!("{address:?}");
+        if accounts.contains_key(&key) {
+            continue;
+        }
+        let pre = diff.pre.get(address).unwrap_or(&empty);
+        let post = diff.post.get(address).unwrap_or(&empty);
+
+        let mut account = Map::new();
+        if post.balance.is_some() && pre.balance != post.balance {
+            account.insert(
+                "balance_ether".to_string(),
+                change(
+                    pre.balance.map(format_ether),
+                    post.balance.map(format_ether),
+                ),
+            );
+        }
+        if post.nonce.is_some() && pre.nonce != post.nonce {
+            account.insert(
+                "nonce".to_string(),
+                change(
+                    pre.nonce.map(|nonce| nonce.to_string()),
+                    post.nonce.map(|nonce| nonce.to_string()),
+                ),
+            );
+        }
+        if post.code.is_some() && pre.code != post.code {
+            account.insert("code_changed".to_string(), json!(true));
+        }
+
+        // Slots missing after the call were cleared to zero.
+        let before = pre.storage.clone().unwrap_or_default();
+        let after = post.storage.clone().unwrap_or_default();
+        let mut storage = Map::new();
+        for slot in before.keys().chain(after.keys()) {
+            let (old, new) = (before.get(slot), after.get(slot));
+            if old == new {
+                continue;
+            }
+            storage.insert(
+                format!("{slot:?}"),
+                change(
+                    Some(format!("{:?}", old.copied().unwrap_or_default())),
+                    Some(format!("{:?}", new.copied().unwrap_or_default())),
+                ),
+            );
+        }
+        if !storage.is_empty() {
+            account.insert("storage".to_string(), Value::Object(storage));
+        }
+
+        accounts.insert(key, Value::Object(account));
+    }
+    Value::Object(accounts)
+}
+
+fn change(before: Option<String>, after: Option<String>) -> Value {
+    json!({ "before": before, "after": after })
+}
@@ -0,0 +1,88 @@
+use super::{confirm, print_json, Cli};
+use crate::{
+    client::{EtherClient, TxKind},
+    contract::{decode_revert, Erc20, Erc721, IERC20_ABI},
+};
+use ethers::types::{TransactionRequest, U256};
+use std::{error::Error, sync::Arc};
+
+/// `token-*` and `nft-*` commands, the first argument is always the token address.
+pub async fn run(cli: &Cli, client: &EtherClient) -> Result<(), Box<dyn Error>> {
+    let address = cli.address(cli.arg(0, "token")?)?;
+    let provider = Arc::new(client.get_provider());
+
+    match cli.command.as_str() {
+        "nft-owner" => {
+            let token_id = U256::from_dec_str(cli.arg(1, "token id")?)?;
+            return print_json(&Erc721::new(provider, address).owner_of(token_id).await?);
+        }
+        "nft-uri" => {
+            let token_id = U256::from_dec_str(cli.arg(1, "token id")?)?;
+            return print_json(&Erc721::new(provider, address).token_uri(token_id).await?);
+        }
+        _ => {}
+    }
+
+    let token = Erc20::new(provider, address).await?;
+    match cli.command.as_str() {
+        "token-info" => print_json(&token.metadata().await?),
+        "token-balance" => {
+            cli.arg(1, "holder")?;
+            let mut balances = Vec::new();
+            for holder in &cli.args[1..] {
+                balances.push(token.balance(cli.address(holder)?).await?);
+            }
+            print_json(&balances)
+        }
+        "token-allowance" => {
+            let owner = cli.address(cli.arg(1, "owner")?)?;
+            let spender = cli.address(cli.arg(2, "spender")?)?;
+            print_json(&token.allowance(owner, spender).await?)
+        }
+        "token-transfer" => {
+            let to = cli.address(cli.arg(1, "to")?)?;
+            let amount = token.parse_amount(cli.arg(2, "amount")?)?;
+            let question = format!("Transfer {}?", token.format_amount(amount)?);
+            send(cli, client, token.transfer(to, amount), &question).await
+        }
+        "token-approve" => {
+            let spender = cli.address(cli.arg(1, "spender")?)?;
+            let amount = token.parse_amount(cli.arg(2, "amount")?)?;
+            let question = match amount == U256::MAX {
+                true => "Approve an unlimited amount?".to_string(),
+                false => format!("Approve {}?", token.format_amount(amount)?),
+            };
+            send(cli, client, token.approve(spender, amount), &question).await
+        }
+        command => Err(format!("unknown command {command}, see --help").into()),
+    }
+}
+
+/// Fee preview, confirmation and sending, as for `send`.
+async fn send(
+    cli: &Cli,
+    client: &EtherClient,
+    request: TransactionRequest,
+    question: &str,
+) -> Result<(), Box<dyn Error>> {
+    let kind: TxKind = cli.option("--type").unwrap_or("1559").parse()?;
+    let mut tx = client.create_contract_tx(request, "0", kind)?;
+
+    let preview = match client.prepare_tx(&mut tx, cli.flag("--access-list")).await {
+        Ok(preview) => preview,
+        Err(err) => {
+            return match err.revert_data() {
+                Some(data) => {
+                    Err(format!("reverted: {}", decode_revert(&IERC20_ABI, &data)).into())
+                }
+                None => Err(err.into()),
+            }
+        }
+    };
+    eprintln!("{}", serde_json::to_string_pretty(&preview)?);
+    if !cli.flag("--yes") && !confirm(question)? {
+        return Err("aborted".into());
+    }
+
+    print_json(&client.send_raw_tx(tx).await?)
+}
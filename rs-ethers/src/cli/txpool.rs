@@ -0,0 +1,385 @@
+use super::{print_json, Cli};
+use crate::client::EtherClient;
+use ethers::{
+    types::{Address, Transaction, TxHash, TxpoolContent, U256},
+    utils::{format_units, hex, id},
+};
+use serde_json::{json, Value};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    error::Error,
+    time::Duration,
+};
+
+/// Seconds between pool snapshots in watch mode.
+const DEFAULT_WATCH_INTERVAL: u64 = 4;
+
+/// `txpool`: summary of the node's pool, the full content with --raw, changes with --watch.
+pub async fn run(cli: &Cli, client: &EtherClient) -> Result<(), Box<dyn Error>> {
+    let filter = PoolFilter {
+        from: cli
+            .option("--from")
+            .map(|from| cli.address(from))
+            .transpose()?,
+        to: cli.option("--to").map(|to| cli.address(to)).transpose()?,
+        selector: cli.option("--selector").map(selector).transpose()?,
+    };
+
+    let mut pool = filter.apply(client.get_tx_pool().await?);
+    match cli.flag("--raw") {
+        true => print_json(&pool)?,
+        false => print_json(&summary(client, &pool).await?)?,
+    }
+    if !cli.flag("--watch") {
+        return Ok(());
+    }
+
+    let interval = match cli.option("--interval") {
+        Some(seconds) => seconds.parse()?,
+        None => DEFAULT_WATCH_INTERVAL,
+    };
+    loop {
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+
+        let next = filter.apply(client.get_tx_pool().await?);
+        for change in diff(&pool, &next)? {
+            println!("{}", serde_json::to_string(&change)?);
+        }
+        pool = next;
+    }
+}
+
+/// `--selector` as hex (`0xa9059cbb`) or as a signature (`transfer(address,uint256)`).
+fn selector(value: &str) -> Result<[u8; 4], Box<dyn Error>> {
+    if value.contains('(') {
+        return Ok(id(value));
+    }
+    let bytes = hex::decode(value)?;
+    bytes
+        .try_into()
+        .map_err(|_| format!("selector {value} is not 4 bytes").into())
+}
+
+struct PoolFilter {
+    from: Option<Address>,
+    to: Option<Address>,
+    selector: Option<[u8; 4]>,
+}
+
+// `Option::is_none_or` needs Rust 1.82.
+#[allow(clippy::unnecessary_map_or)]
+impl PoolFilter {
+    fn apply(&self, mut pool: TxpoolContent) -> TxpoolContent {
+        for txs in [&mut pool.pending, &mut pool.queued] {
+            txs.retain(|from, _| self.from.map_or(true, |wanted| *from == wanted));
+            for by_nonce in txs.values_mut() {
+                by_nonce.retain(|_, tx| self.matches(tx));
+            }
+            txs.retain(|_, by_nonce| !by_nonce.is_empty());
+        }
+        pool
+    }
+
+    fn matches(&self, tx: &Transaction) -> bool {
+        self.to.map_or(true, |to| tx.to == Some(to))
+            && self
+                .selector
+                .map_or(true, |selector| tx.input.starts_with(&selector))
+    }
+}
+
+async fn summary(client: &EtherClient, pool: &TxpoolContent) -> Result<Value, Box<dyn Error>> {
+    let mut senders = Vec::new();
+    let addresses: BTreeSet<&Address> = pool.pending.keys().chain(pool.queued.keys()).collect();
+
+    for from in addresses {
+        let pending = nonces(pool.pending.get(from));
+        let queued = nonces(pool.queued.get(from));
+
+        let mut all: Vec<u64> = pending.iter().chain(queued.iter()).copied().collect();
+        all.sort_unstable();
+        let next = client.get_transaction_count(*from).await?.as_u64();
+        let gaps = nonce_gaps(next, &all);
+
+        senders.push(json!({
+            "from": from,
+            "pending": pending.len(),
+            "queued": queued.len(),
+            "account_nonce": next,
+            "nonces": all,
+            "nonce_gaps": gaps,
+        }));
+    }
+    senders.sort_by_key(|sender| {
+        std::cmp::Reverse(
+            sender["pending"].as_u64().unwrap_or_default()
+                + sender["queued"].as_u64().unwrap_or_default(),
+        )
+    });
+
+    let transactions = || {
+        pool.pending
+            .values()
+            .chain(pool.queued.values())
+            .flat_map(BTreeMap::values)
+    };
+    let gas_prices: Vec<U256> = transactions().filter_map(max_fee).collect();
+    let priority_fees: Vec<U256> = transactions()
+        .filter_map(|tx| tx.max_priority_fee_per_gas)
+        .collect();
+
+    Ok(json!({
+        "pending": pool.pending.values().map(BTreeMap::len).sum::<usize>(),
+        "queued": pool.queued.values().map(BTreeMap::len).sum::<usize>(),
+        "senders": senders,
+        "gas_price_gwei": distribution(gas_prices)?,
+        "priority_fee_gwei": distribution(priority_fees)?,
+    }))
+}
+
+fn nonces(txs: Option<&BTreeMap<String, Transaction>>) -> Vec<u64> {
+    txs.into_iter()
+        .flat_map(BTreeMap::keys)
+        .filter_map(|nonce| nonce.parse().ok())
+        .collect()
+}
+
+/// Queued transactions wait for the nonces missing between the account's next
+/// nonce and the highest one in the pool, `nonces` sorted.
+fn nonce_gaps(next: u64, nonces: &[u64]) -> Vec<u64> {
+    match nonces.last() {
+        Some(last) => (next..*last)
+            .filter(|nonce| nonces.binary_search(nonce).is_err())
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Most the transaction pays per gas: the max fee for EIP-1559, else the gas price.
+fn max_fee(tx: &Transaction) -> Option<U256> {
+    tx.max_fee_per_gas.or(tx.gas_price)
+}
+
+/// Min, quartiles and max of `values`, null for an empty pool.
+fn distribution(mut values: Vec<U256>) -> Result<Value, Box<dyn Error>> {
+    if values.is_empty() {
+        return Ok(Value::Null);
+    }
+    values.sort_unstable();
+
+    let at = |percent: usize| -> Result<String, Box<dyn Error>> {
+        Ok(format_units(
+            values[(values.len() - 1) * percent / 100],
+            "gwei",
+        )?)
+    };
+    Ok(json!({
+        "min": at(0)?,
+        "p25": at(25)?,
+        "median": at(50)?,
+        "p75": at(75)?,
+        "max": at(100)?,
+    }))
+}
+
+/// Transactions that entered or left the pool, or moved between pending and queued.
+fn diff(old: &TxpoolContent, new: &TxpoolContent) -> Result<Vec<Value>, Box<dyn Error>> {
+    let (before, after) = (locations(old), locations(new));
+    let mut changes = Vec::new();
+
+    for (hash, (pool, tx)) in &after {
+        let event = match before.get(hash) {
+            None => "added",
+            Some((old_pool, _)) if old_pool == pool => continue,
+            Some(_) if *pool == "pending" => "promoted",
+            Some(_) => "demoted",
+        };
+        changes.push(change(event, pool, tx)?);
+    }
+    for (hash, (pool, tx)) in &before {
+        if !after.contains_key(hash) {
+            changes.push(change("removed", pool, tx)?);
+        }
+    }
+    Ok(changes)
+}
+
+fn locations(pool: &TxpoolContent) -> HashMap<TxHash, (&'static str, &Transaction)> {
+    let pending = pool
+        .pending
+        .values()
+        .flat_map(BTreeMap::values)
+        .map(|tx| ("pending", tx));
+    let queued = pool
+        .queued
+        .values()
+        .flat_map(BTreeMap::values)
+        .map(|tx| ("queued", tx));
+
+    pending
+        .chain(queued)
+        .map(|(name, tx)| (tx.hash, (name, tx)))
+        .collect()
+}
+
+fn change(event: &str, pool: &str, tx: &Transaction) -> Result<Value, Box<dyn Error>> {
+    Ok(json!({
+        "event": event,
+        "pool": pool,
+        "hash": tx.hash,
+        "from": tx.from,
+        "nonce": tx.nonce.as_u64(),
+        "to": tx.to,
+        "selector": tx.input.get(..4).map(hex::encode_prefixed),
+        "max_fee_gwei": max_fee(tx).map(|fee| format_units(fee, "gwei")).transpose()?,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::H256;
+
+    const ALICE: Address = Address::repeat_byte(0xa1);
+    const BOB: Address = Address::repeat_byte(0xb0);
+    const TOKEN: Address = Address::repeat_byte(0x70);
+
+    fn tx(id: u8, from: Address, nonce: u64, to: Address, input: &str) -> Transaction {
+        Transaction {
+            hash: H256::repeat_byte(id),
+            from,
+            nonce: nonce.into(),
+            to: Some(to),
+            input: hex::decode(input).unwrap().into(),
+            max_fee_per_gas: Some(U256::from(id) * 1_000_000_000u64),
+            ..Default::default()
+        }
+    }
+
+    fn pool(pending: &[&Transaction], queued: &[&Transaction]) -> TxpoolContent {
+        let by_sender = |txs: &[&Transaction]| {
+            let mut map: BTreeMap<Address, BTreeMap<String, Transaction>> = BTreeMap::new();
+            for tx in txs {
+                map.entry(tx.from)
+                    .or_default()
+                    .insert(tx.nonce.to_string(), (*tx).clone());
+            }
+            map
+        };
+        TxpoolContent {
+            pending: by_sender(pending),
+            queued: by_sender(queued),
+        }
+    }
+
+    #[test]
+    fn finds_nonce_gaps() {
+        let cases: [(u64, &[u64], &[u64]); 5] = [
+            (5, &[], &[]),
+            (5, &[5, 6, 7], &[]),
+            (5, &[5, 7, 10], &[6, 8, 9]),
+            (3, &[6], &[3, 4, 5]),
+            // Nonces the account already used do not count.
+            (8, &[5, 6], &[]),
+        ];
+
+        for (next, nonces, gaps) in cases {
+            assert_eq!(nonce_gaps(next, nonces), gaps, "{next} {nonces:?}");
+        }
+    }
+
+    #[test]
+    fn summarises_the_price_distribution() {
+        assert_eq!(distribution(Vec::new()).unwrap(), Value::Null);
+
+        let gwei = |amount: u64| U256::from(amount) * 1_000_000_000u64;
+        let values = [5, 1, 4, 2, 3].map(gwei).to_vec();
+        assert_eq!(
+            distribution(values).unwrap(),
+            json!({
+                "min": "1.000000000",
+                "p25": "2.000000000",
+                "median": "3.000000000",
+                "p75": "4.000000000",
+                "max": "5.000000000",
+            })
+        );
+        assert_eq!(distribution(vec![gwei(7)]).unwrap()["p25"], "7.000000000");
+    }
+
+    #[test]
+    fn filters_the_pool() {
+        let transfer = hex::encode(id("transfer(address,uint256)"));
+        let a = tx(1, ALICE, 0, TOKEN, &transfer);
+        let b = tx(2, ALICE, 1, BOB, "");
+        let c = tx(3, BOB, 4, TOKEN, "095ea7b3");
+        let content = pool(&[&a, &b], &[&c]);
+
+        let filter = PoolFilter {
+            from: None,
+            to: Some(TOKEN),
+            selector: None,
+        };
+        assert_eq!(
+            locations(&filter.apply(content.clone())).len(),
+            2,
+            "to filter"
+        );
+
+        let filter = PoolFilter {
+            from: Some(ALICE),
+            to: None,
+            selector: Some(selector("transfer(address,uint256)").unwrap()),
+        };
+        let filtered = filter.apply(content);
+        assert_eq!(filtered.pending.len(), 1);
+        assert!(filtered.queued.is_empty());
+        assert_eq!(filtered.pending[&ALICE].len(), 1);
+        assert_eq!(filtered.pending[&ALICE]["0"].hash, a.hash);
+
+        assert_eq!(
+            selector("0xa9059cbb").unwrap(),
+            id("transfer(address,uint256)")
+        );
+        assert!(selector("0xa9059c").is_err());
+    }
+
+    #[test]
+    fn reports_pool_changes() {
+        let kept = tx(1, ALICE, 0, TOKEN, "a9059cbb0000");
+        let promoted = tx(2, ALICE, 1, TOKEN, "");
+        let demoted = tx(3, BOB, 5, TOKEN, "");
+        let removed = tx(4, BOB, 4, TOKEN, "");
+        let added = tx(5, BOB, 6, TOKEN, "");
+
+        let old = pool(&[&kept, &demoted, &removed], &[&promoted]);
+        let new = pool(&[&kept, &promoted], &[&demoted, &added]);
+
+        let mut changes: Vec<(String, String, H256)> = diff(&old, &new)
+            .unwrap()
+            .into_iter()
+            .map(|change| {
+                (
+                    change["event"].as_str().unwrap().to_string(),
+                    change["pool"].as_str().unwrap().to_string(),
+                    serde_json::from_value(change["hash"].clone()).unwrap(),
+                )
+            })
+            .collect();
+        changes.sort();
+
+        let expected = [
+            ("added", "queued", &added),
+            ("demoted", "queued", &demoted),
+            ("promoted", "pending", &promoted),
+            ("removed", "pending", &removed),
+        ]
+        .map(|(event, pool, tx)| (event.to_string(), pool.to_string(), tx.hash));
+        assert_eq!(changes, expected);
+
+        assert!(diff(&new, &new).unwrap().is_empty());
+        let change = change("added", "queued", &kept).unwrap();
+        assert_eq!(change["selector"], "0xa9059cbb");
+        assert_eq!(change["max_fee_gwei"], "1.000000000");
+    }
+}
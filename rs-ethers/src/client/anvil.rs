@@ -0,0 +1,425 @@
+use super::{ClientError, EtherClient};
+use ethers::{
+    prelude::*,
+    signers::{coins_bip39::English, MnemonicBuilder},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    fs,
+    io::Read,
+    net::{TcpListener, TcpStream},
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Anvil's default dev mnemonic, passed explicitly so the session keys are known.
+const DEV_MNEMONIC: &str = "test test test test test test test test test test test junk";
+
+/// Port of a persistent node when --port is not given, Anvil's own default.
+const DEFAULT_ANVIL_PORT: u16 = 8545;
+
+/// How long a node gets to start listening or to shut down and dump its state.
+const ANVIL_WAIT: Duration = Duration::from_secs(15);
+
+/// Settings of a spawned Anvil node, `None` leaves Anvil's default.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AnvilConfig {
+    pub port: Option<u16>,
+    pub chain_id: Option<u64>,
+    /// Seconds between blocks, Anvil mines on every transaction without it.
+    pub block_time: Option<u64>,
+    pub accounts: Option<u32>,
+    pub fork_url: Option<String>,
+    pub fork_block: Option<u64>,
+    /// State file loaded on start and, for persistent nodes, written on stop.
+    pub state: Option<PathBuf>,
+}
+
+impl AnvilConfig {
+    /// Throwaway node for one command, killed when the node is dropped.
+    pub fn spawn(&self) -> Result<ThrowawayNode, ClientError> {
+        let port = match self.port {
+            Some(port) if TcpStream::connect(("127.0.0.1", port)).is_ok() => {
+                return Err(ClientError::Anvil(format!(
+                    "port {port} is already in use, pick another with --port"
+                )))
+            }
+            Some(port) => port,
+            None => free_port()?,
+        };
+
+        let mut command = Command::new("anvil");
+        command
+            .args(["--port", &port.to_string(), "--mnemonic", DEV_MNEMONIC])
+            .args(self.args())
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped());
+        // The node is killed rather than stopped, so there is nothing to dump on exit.
+        if let Some(state) = self.state.as_ref().filter(|state| state.exists()) {
+            command.arg("--load-state").arg(state);
+        }
+
+        let child = command.spawn().map_err(|err| {
+            ClientError::Anvil(format!(
+                "could not run anvil: {err}, install Foundry or pass --rpc <url>"
+            ))
+        })?;
+        let mut node = ThrowawayNode { child, port };
+
+        if let Err(reason) = wait_until_listening(&mut node.child, port) {
+            let mut output = String::new();
+            if let Some(mut stderr) = node.child.stderr.take() {
+                let _ = stderr.read_to_string(&mut output);
+            }
+            return Err(ClientError::Anvil(match output.trim() {
+                "" => reason,
+                output => format!("{reason}: {output}"),
+            }));
+        }
+        Ok(node)
+    }
+
+    fn args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(chain_id) = self.chain_id {
+            args.extend(["--chain-id".to_string(), chain_id.to_string()]);
+        }
+        if let Some(block_time) = self.block_time {
+            args.extend(["--block-time".to_string(), block_time.to_string()]);
+        }
+        if let Some(accounts) = self.accounts {
+            args.extend(["--accounts".to_string(), accounts.to_string()]);
+        }
+        if let Some(fork_url) = &self.fork_url {
+            args.extend(["--fork-url".to_string(), fork_url.clone()]);
+        }
+        if let Some(fork_block) = self.fork_block {
+            args.extend(["--fork-block-number".to_string(), fork_block.to_string()]);
+        }
+        args
+    }
+}
+
+/// Anvil node started by `anvil-start` that outlives the command, found again
+/// by later commands through its session file.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AnvilSession {
+    pub pid: u32,
+    pub port: u16,
+    pub log: PathBuf,
+    pub config: AnvilConfig,
+}
+
+impl AnvilSession {
+    /// Per user directory of the session files, one `<port>.json` and `<port>.log` per node.
+    pub fn dir() -> PathBuf {
+        match std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE")) {
+            Some(home) => PathBuf::from(home).join(".rs-ethers").join("anvil"),
+            None => std::env::temp_dir().join("rs-ethers-anvil"),
+        }
+    }
+
+    pub fn path(port: u16) -> PathBuf {
+        AnvilSession::dir().join(format!("{port}.json"))
+    }
+
+    /// Starts Anvil in the background and waits until it listens.
+    pub fn start(config: AnvilConfig) -> Result<AnvilSession, ClientError> {
+        let port = config.port.unwrap_or(DEFAULT_ANVIL_PORT);
+        if let Some(session) = AnvilSession::load(Some(port))? {
+            return Err(ClientError::Anvil(format!(
+                "already running at {} (pid {}), stop it first",
+                session.endpoint(),
+                session.pid
+            )));
+        }
+        if TcpStream::connect(("127.0.0.1", port)).is_ok() {
+            return Err(ClientError::Anvil(format!(
+                "port {port} is already in use, pick another with --port"
+            )));
+        }
+
+        fs::create_dir_all(AnvilSession::dir())
+            .map_err(|err| ClientError::Anvil(err.to_string()))?;
+        let log = AnvilSession::dir().join(format!("{port}.log"));
+        let output = fs::File::create(&log).map_err(|err| ClientError::Anvil(err.to_string()))?;
+        let errors = output
+            .try_clone()
+            .map_err(|err| ClientError::Anvil(err.to_string()))?;
+
+        let mut command = Command::new("anvil");
+        command
+            .args(["--port", &port.to_string(), "--mnemonic", DEV_MNEMONIC])
+            .args(config.args())
+            .stdin(Stdio::null())
+            .stdout(output)
+            .stderr(errors);
+        if let Some(state) = &config.state {
+            command.arg("--state").arg(state);
+        }
+        // Own process group, so a Ctrl-C in the terminal does not reach the node.
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(&mut command, 0);
+
+        let mut child = command
+            .spawn()
+            .map_err(|err| ClientError::Anvil(format!("could not run anvil: {err}")))?;
+        let session = AnvilSession {
+            pid: child.id(),
+            port,
+            log,
+            config,
+        };
+
+        if let Err(reason) = wait_until_listening(&mut child, port) {
+            return Err(ClientError::Anvil(format!(
+                "{reason}, see {}",
+                session.log.display()
+            )));
+        }
+
+        let json = serde_json::to_string_pretty(&session)
+            .map_err(|err| ClientError::Anvil(err.to_string()))?;
+        fs::write(AnvilSession::path(port), json)
+            .map_err(|err| ClientError::Anvil(err.to_string()))?;
+        Ok(session)
+    }
+
+    /// The running session on `port`, or the first one on any port, `None` when there
+    /// is none or its node is gone, including when its pid now belongs to another process.
+    pub fn load(port: Option<u16>) -> Result<Option<AnvilSession>, ClientError> {
+        if let Some(port) = port {
+            return AnvilSession::load_file(&AnvilSession::path(port));
+        }
+
+        let Ok(entries) = fs::read_dir(AnvilSession::dir()) else {
+            return Ok(None);
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == "json")
+            })
+            .collect();
+        paths.sort();
+        for path in paths {
+            if let Some(session) = AnvilSession::load_file(&path)? {
+                return Ok(Some(session));
+            }
+        }
+        Ok(None)
+    }
+
+    fn load_file(path: &Path) -> Result<Option<AnvilSession>, ClientError> {
+        let Ok(json) = fs::read_to_string(path) else {
+            return Ok(None);
+        };
+        let session: AnvilSession = serde_json::from_str(&json)
+            .map_err(|err| ClientError::Anvil(format!("{}: {err}", path.display())))?;
+
+        if !session.is_running() || !session.is_listening() {
+            let _ = fs::remove_file(path);
+            return Ok(None);
+        }
+        Ok(Some(session))
+    }
+
+    /// Interrupts the node like a Ctrl-C, so it writes its state file before it exits.
+    #[cfg(unix)]
+    pub fn stop(&self) -> Result<(), ClientError> {
+        if !self.is_running() {
+            let _ = fs::remove_file(AnvilSession::path(self.port));
+            return Err(ClientError::Anvil(format!(
+                "pid {} is no longer the session's anvil, not stopping it",
+                self.pid
+            )));
+        }
+
+        let status = Command::new("kill")
+            .args(["-INT", &self.pid.to_string()])
+            .status()
+            .map_err(|err| ClientError::Anvil(err.to_string()))?;
+        if !status.success() {
+            return Err(ClientError::Anvil(format!(
+                "could not stop pid {}",
+                self.pid
+            )));
+        }
+
+        let started = Instant::now();
+        while self.is_listening() && started.elapsed() < ANVIL_WAIT {
+            thread::sleep(Duration::from_millis(100));
+        }
+        let _ = fs::remove_file(AnvilSession::path(self.port));
+        Ok(())
+    }
+
+    /// Without signals there is no way to make Anvil write its state on exit.
+    #[cfg(not(unix))]
+    pub fn stop(&self) -> Result<(), ClientError> {
+        Err(ClientError::Anvil(format!(
+            "stopping a session needs a Unix system, press Ctrl-C in anvil (pid {}) instead",
+            self.pid
+        )))
+    }
+
+    /// Whether the pid still runs Anvil on the session's port, pids get reused.
+    #[cfg(unix)]
+    fn is_running(&self) -> bool {
+        let Ok(output) = Command::new("ps")
+            .args(["-p", &self.pid.to_string(), "-o", "command="])
+            .output()
+        else {
+            return false;
+        };
+        let command = String::from_utf8_lossy(&output.stdout);
+        command.contains("anvil") && command.contains(&format!("--port {}", self.port))
+    }
+
+    #[cfg(not(unix))]
+    fn is_running(&self) -> bool {
+        let Ok(output) = Command::new("tasklist")
+            .args(["/FI", &format!("PID eq {}", self.pid), "/NH"])
+            .output()
+        else {
+            return false;
+        };
+        String::from_utf8_lossy(&output.stdout).contains("anvil")
+    }
+
+    pub fn is_listening(&self) -> bool {
+        TcpStream::connect(("127.0.0.1", self.port)).is_ok()
+    }
+
+    pub fn endpoint(&self) -> String {
+        format!("http://localhost:{}", self.port)
+    }
+
+    pub fn ws_endpoint(&self) -> String {
+        format!("ws://localhost:{}", self.port)
+    }
+}
+
+/// Anvil node spawned for a single command, killed when dropped.
+pub struct ThrowawayNode {
+    child: Child,
+    port: u16,
+}
+
+impl ThrowawayNode {
+    pub fn endpoint(&self) -> String {
+        format!("http://localhost:{}", self.port)
+    }
+
+    pub fn ws_endpoint(&self) -> String {
+        format!("ws://localhost:{}", self.port)
+    }
+}
+
+impl Drop for ThrowawayNode {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Anvil node a command runs against when no endpoint is given.
+pub enum LocalNode {
+    Throwaway(ThrowawayNode),
+    Session(AnvilSession),
+}
+
+impl LocalNode {
+    pub fn endpoint(&self) -> String {
+        match self {
+            LocalNode::Throwaway(node) => node.endpoint(),
+            LocalNode::Session(session) => session.endpoint(),
+        }
+    }
+
+    pub fn ws_endpoint(&self) -> String {
+        match self {
+            LocalNode::Throwaway(node) => node.ws_endpoint(),
+            LocalNode::Session(session) => session.ws_endpoint(),
+        }
+    }
+
+    /// First dev account of the node, both kinds run with the same mnemonic.
+    pub fn dev_wallet(&self) -> Result<LocalWallet, ClientError> {
+        Ok(MnemonicBuilder::<English>::default()
+            .phrase(DEV_MNEMONIC)
+            .build()?)
+    }
+}
+
+/// Waits until `child` listens on `port`, killing it when it takes too long.
+/// An exited child means whatever listens on the port is not ours.
+fn wait_until_listening(child: &mut Child, port: u16) -> Result<(), String> {
+    let started = Instant::now();
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => return Err(format!("anvil exited with {status}")),
+            Ok(None) => {}
+            Err(err) => return Err(err.to_string()),
+        }
+        if TcpStream::connect(("127.0.0.1", port)).is_ok() {
+            return Ok(());
+        }
+        if started.elapsed() > ANVIL_WAIT {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(format!("anvil did not listen on port {port}"));
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+}
+
+/// A port nothing listens on, for throwaway nodes without --port.
+fn free_port() -> Result<u16, ClientError> {
+    TcpListener::bind(("127.0.0.1", 0))
+        .and_then(|listener| listener.local_addr())
+        .map(|address| address.port())
+        .map_err(|err| ClientError::Anvil(format!("no free port: {err}")))
+}
+
+impl EtherClient {
+    /// `evm_snapshot`, the id reverts the chain to the current state once.
+    pub async fn snapshot(&self) -> Result<U256, ClientError> {
+        Ok(self.provider.request("evm_snapshot", ()).await?)
+    }
+
+    /// `evm_revert`, `false` when the snapshot is unknown or already used.
+    pub async fn revert_to_snapshot(&self, id: U256) -> Result<bool, ClientError> {
+        Ok(self.provider.request("evm_revert", [id]).await?)
+    }
+
+    /// `evm_increaseTime`, returns the total offset from wall clock time in seconds.
+    pub async fn increase_time(&self, seconds: u64) -> Result<i64, ClientError> {
+        Ok(self
+            .provider
+            .request("evm_increaseTime", [U256::from(seconds)])
+            .await?)
+    }
+
+    /// `evm_mine`, one block per call.
+    pub async fn mine(&self, blocks: u64) -> Result<(), ClientError> {
+        for _ in 0..blocks {
+            self.provider.request::<_, Value>("evm_mine", ()).await?;
+        }
+        Ok(())
+    }
+
+    /// `anvil_dumpState`, the chain state as an opaque blob for `load_state`.
+    pub async fn dump_state(&self) -> Result<Bytes, ClientError> {
+        Ok(self.provider.request("anvil_dumpState", ()).await?)
+    }
+
+    pub async fn load_state(&self, state: Bytes) -> Result<bool, ClientError> {
+        Ok(self.provider.request("anvil_loadState", [state]).await?)
+    }
+}
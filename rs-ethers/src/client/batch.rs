@@ -0,0 +1,136 @@
+use super::{ClientError, EtherClient};
+use ethers::{
+    abi::{AbiDecode, AbiEncode},
+    contract::{
+        multicall_contract::{Aggregate3Call, Aggregate3Return, Call3},
+        MULTICALL_ADDRESS,
+    },
+    prelude::*,
+    providers::JsonRpcError,
+    types::transaction::eip2718::TypedTransaction,
+};
+use serde_json::{json, Value};
+
+/// Output of one batched call: the return data, or the revert data when it failed.
+pub type CallOutput = std::result::Result<Bytes, Bytes>;
+
+/// Runtime code set at the Multicall3 address on Anvil nodes that lack it. It only
+/// implements `aggregate3`, with Multicall3's ABI and semantics: every call is made
+/// in order, a failed call reverts the batch with its revert data unless
+/// `allowFailure` is set. Any other selector reverts.
+const MULTICALL3_RUNTIME: &str = concat!(
+    "0x60003560e01c6382ad56cb1461001457600080fd5b600435600401803590602001602060005281",
+    "6020528160051b60400160005b838110156100c3578060051b830135830180604001358101803580",
+    "826020018660600137600060008287606001600087355af18061007e57836020013561007e573d60",
+    "00803e3d6000fd5b8552505050604082602001523d82604001523d6000836060013e60003d830160",
+    "600152604082038160051b60400152903d601f01601f19160160600190600101610033565b506000",
+    "f3",
+);
+
+impl EtherClient {
+    /// Runs `calls` (target, calldata) in one `eth_call` to Multicall3's `aggregate3`.
+    pub async fn multicall(
+        &self,
+        calls: &[(Address, Bytes)],
+        block: Option<BlockId>,
+    ) -> Result<Vec<CallOutput>, ClientError> {
+        let address = self.ensure_multicall().await?;
+
+        let aggregate = Aggregate3Call {
+            calls: calls
+                .iter()
+                .map(|(target, data)| Call3 {
+                    target: *target,
+                    allow_failure: true,
+                    call_data: data.clone(),
+                })
+                .collect(),
+        };
+        let tx: TypedTransaction = TransactionRequest::new()
+            .to(address)
+            .data(aggregate.encode())
+            .into();
+        let output = self.provider.call(&tx, block).await?;
+
+        let results = Aggregate3Return::decode(&output)
+            .map_err(|err| ProviderError::CustomError(format!("bad aggregate3 output: {err}")))?;
+        Ok(results
+            .return_data
+            .into_iter()
+            .map(|result| match result.success {
+                true => Ok(result.return_data),
+                false => Err(result.return_data),
+            })
+            .collect())
+    }
+
+    /// Runs `calls` as one JSON-RPC batch of `eth_call`, for chains without Multicall3.
+    pub async fn batch_call(
+        &self,
+        calls: &[(Address, Bytes)],
+        block: Option<BlockId>,
+    ) -> Result<Vec<CallOutput>, ClientError> {
+        let block = block.unwrap_or(BlockNumber::Latest.into());
+        let requests: Vec<(String, Value)> = calls
+            .iter()
+            .map(|(target, data)| {
+                let call = json!({ "to": target, "data": data });
+                ("eth_call".to_string(), json!([call, block]))
+            })
+            .collect();
+
+        self.batch_request(&requests)
+            .await?
+            .into_iter()
+            .map(|response| match response {
+                Ok(output) => Ok(Ok(serde_json::from_value(output)
+                    .map_err(|err| ProviderError::CustomError(err.to_string()))?)),
+                Err(err) => match err.as_revert_data() {
+                    Some(data) => Ok(Err(data)),
+                    None => Err(ProviderError::CustomError(err.to_string()).into()),
+                },
+            })
+            .collect()
+    }
+
+    /// Any JSON-RPC requests as one batch, each with its own result or error.
+    pub async fn batch_request(
+        &self,
+        requests: &[(String, Value)],
+    ) -> Result<Vec<Result<Value, JsonRpcError>>, ClientError> {
+        self.provider
+            .as_ref()
+            .batch(requests)
+            .await
+            .map_err(|err| ProviderError::from(err).into())
+    }
+
+    /// Address of Multicall3, installed at its canonical address first on Anvil nodes
+    /// that lack it (fresh nodes, or forks of chains it is not deployed on).
+    pub async fn ensure_multicall(&self) -> Result<Address, ClientError> {
+        if !self.get_code(MULTICALL_ADDRESS).await?.is_empty() {
+            return Ok(MULTICALL_ADDRESS);
+        }
+
+        let version = self.provider.client_version().await?;
+        if !version.to_lowercase().starts_with("anvil") {
+            return Err(ClientError::MulticallUnavailable(format!(
+                "no code at {MULTICALL_ADDRESS:?} on this chain, use the JSON-RPC batch mode"
+            )));
+        }
+
+        let runtime: Bytes = MULTICALL3_RUNTIME
+            .parse()
+            .map_err(|err| ClientError::MulticallUnavailable(format!("{err}")))?;
+        self.provider
+            .request::<_, ()>("anvil_setCode", (MULTICALL_ADDRESS, runtime))
+            .await?;
+        if self.get_code(MULTICALL_ADDRESS).await?.is_empty() {
+            return Err(ClientError::MulticallUnavailable(format!(
+                "anvil_setCode left no code at {MULTICALL_ADDRESS:?}"
+            )));
+        }
+
+        Ok(MULTICALL_ADDRESS)
+    }
+}
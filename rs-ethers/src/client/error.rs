@@ -0,0 +1,93 @@
+use ethers::{
+    providers::{ProviderError, RpcError},
+    signers::WalletError,
+    types::Bytes,
+};
+use std::{error::Error, fmt};
+
+#[derive(Debug)]
+pub enum ClientError {
+    /// The endpoint could not be reached or understood.
+    NotConnected {
+        end_point: String,
+        reason: String,
+    },
+    /// The call needs a signer and none was loaded.
+    NoSigner,
+    InvalidAddress(String),
+    /// Amount or unit that `parse_units`/`format_units` refused.
+    InvalidUnit(String),
+    BlockNotFound(u64),
+    /// Subscriptions were requested over a transport without pubsub.
+    PubsubUnsupported,
+    Signer(WalletError),
+    /// Signing or broadcasting a transaction failed in the middleware stack.
+    Transaction(String),
+    /// Multicall3 is not deployed, and the node is not Anvil or did not take its code.
+    MulticallUnavailable(String),
+    /// ENS is not deployed on the chain or has no record for the name or address.
+    Ens(String),
+    /// A persistent Anvil node could not be started, found or stopped.
+    Anvil(String),
+    Provider(ProviderError),
+}
+
+impl ClientError {
+    /// Raw revert data when a call or gas estimate was reverted by the EVM.
+    pub fn revert_data(&self) -> Option<Bytes> {
+        match self {
+            ClientError::Provider(err) => err.as_error_response()?.as_revert_data(),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::NotConnected { end_point, reason } => {
+                write!(f, "could not connect to {end_point}: {reason}")
+            }
+            ClientError::NoSigner => write!(f, "no signer loaded"),
+            ClientError::InvalidAddress(address) => write!(
+                f,
+                "invalid address {address}, not hex, an address book label or a resolved ENS name"
+            ),
+            ClientError::InvalidUnit(reason) => write!(f, "invalid amount or unit: {reason}"),
+            ClientError::BlockNotFound(number) => write!(f, "block {number} not found"),
+            ClientError::PubsubUnsupported => {
+                write!(f, "subscriptions need a ws:// or IPC endpoint")
+            }
+            ClientError::Signer(err) => write!(f, "signer error: {err}"),
+            ClientError::Transaction(reason) => write!(f, "transaction failed: {reason}"),
+            ClientError::MulticallUnavailable(reason) => {
+                write!(f, "Multicall3 is not available: {reason}")
+            }
+            ClientError::Ens(reason) => write!(f, "ENS: {reason}"),
+            ClientError::Anvil(reason) => write!(f, "anvil: {reason}"),
+            ClientError::Provider(err) => write!(f, "provider error: {err}"),
+        }
+    }
+}
+
+impl Error for ClientError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ClientError::Signer(err) => Some(err),
+            ClientError::Provider(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<ProviderError> for ClientError {
+    fn from(err: ProviderError) -> Self {
+        ClientError::Provider(err)
+    }
+}
+
+impl From<WalletError> for ClientError {
+    fn from(err: WalletError) -> Self {
+        ClientError::Signer(err)
+    }
+}
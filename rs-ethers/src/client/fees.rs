@@ -0,0 +1,282 @@
+use super::ClientError;
+use ethers::{
+    types::{
+        transaction::eip2718::TypedTransaction, Eip1559TransactionRequest,
+        Eip2930TransactionRequest, FeeHistory, TransactionRequest, U256,
+    },
+    utils::{format_ether, format_units},
+};
+use serde::Serialize;
+use std::str::FromStr;
+
+/// Number of recent blocks `eth_feeHistory` is asked about.
+pub const FEE_HISTORY_BLOCKS: u64 = 10;
+/// Percentile of the paid priority fees used as the suggested tip.
+pub const REWARD_PERCENTILE: f64 = 50.0;
+/// Tip used when the sampled blocks carry no priority fees at all (e.g. a fresh Anvil).
+const FALLBACK_PRIORITY_FEE: u64 = 1_000_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TxKind {
+    Legacy,
+    Eip2930,
+    Eip1559,
+}
+
+impl FromStr for TxKind {
+    type Err = String;
+
+    fn from_str(kind: &str) -> Result<Self, Self::Err> {
+        match kind {
+            "legacy" | "0" => Ok(TxKind::Legacy),
+            "2930" | "eip2930" | "1" => Ok(TxKind::Eip2930),
+            "1559" | "eip1559" | "2" => Ok(TxKind::Eip1559),
+            _ => Err(format!(
+                "unknown transaction type {kind}, use legacy, 2930 or 1559"
+            )),
+        }
+    }
+}
+
+impl TxKind {
+    pub fn build(self, tx: TransactionRequest) -> TypedTransaction {
+        match self {
+            TxKind::Legacy => TypedTransaction::Legacy(tx),
+            TxKind::Eip2930 => {
+                TypedTransaction::Eip2930(Eip2930TransactionRequest::new(tx, Default::default()))
+            }
+            TxKind::Eip1559 => {
+                let mut request = Eip1559TransactionRequest::new();
+                request.from = tx.from;
+                request.to = tx.to;
+                request.value = tx.value;
+                request.data = tx.data;
+                request.nonce = tx.nonce;
+                TypedTransaction::Eip1559(request)
+            }
+        }
+    }
+}
+
+/// Fees derived from `eth_feeHistory`, plus the node gas price for pre-1559 types.
+#[derive(Debug, Clone)]
+pub struct FeeEstimate {
+    /// Base fee of the next block, the last entry of the history.
+    pub base_fee: U256,
+    pub max_priority_fee_per_gas: U256,
+    /// Leaves room for the base fee to double before the transaction stalls.
+    pub max_fee_per_gas: U256,
+    pub gas_price: U256,
+}
+
+impl FeeEstimate {
+    pub fn from_history(history: &FeeHistory, gas_price: U256) -> FeeEstimate {
+        let base_fee = history.base_fee_per_gas.last().copied().unwrap_or_default();
+
+        let mut rewards: Vec<U256> = history
+            .reward
+            .iter()
+            .filter_map(|block| block.first().copied())
+            .filter(|reward| !reward.is_zero())
+            .collect();
+        rewards.sort();
+        let max_priority_fee_per_gas = match rewards.get(rewards.len() / 2) {
+            Some(reward) => *reward,
+            None => U256::from(FALLBACK_PRIORITY_FEE),
+        };
+
+        FeeEstimate {
+            base_fee,
+            max_priority_fee_per_gas,
+            max_fee_per_gas: base_fee * 2 + max_priority_fee_per_gas,
+            gas_price,
+        }
+    }
+
+    pub fn apply(&self, tx: &mut TypedTransaction) {
+        match tx {
+            TypedTransaction::Eip1559(request) => {
+                request.max_fee_per_gas = Some(self.max_fee_per_gas);
+                request.max_priority_fee_per_gas = Some(self.max_priority_fee_per_gas);
+            }
+            _ => {
+                tx.set_gas_price(self.gas_price);
+            }
+        }
+    }
+}
+
+/// What a transaction is expected to cost, shown before it is signed.
+#[derive(Debug, Serialize)]
+pub struct FeePreview {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub from: String,
+    /// `None` for contract creation.
+    pub to: Option<String>,
+    pub value_ether: String,
+    pub gas_limit: String,
+    pub base_fee_gwei: String,
+    pub max_fee_per_gas_gwei: Option<String>,
+    pub max_priority_fee_per_gas_gwei: Option<String>,
+    pub gas_price_gwei: Option<String>,
+    pub access_list_entries: usize,
+    /// Value plus gas at the current base fee and tip.
+    pub expected_cost_ether: String,
+    /// Value plus gas at the highest price the transaction allows.
+    pub max_cost_ether: String,
+}
+
+impl FeePreview {
+    pub fn new(tx: &TypedTransaction, fees: &FeeEstimate) -> Result<FeePreview, ClientError> {
+        let gas = tx.gas().copied().unwrap_or_default();
+        let value = tx.value().copied().unwrap_or_default();
+        let gwei = |amount: U256| {
+            format_units(amount, "gwei").map_err(|err| ClientError::InvalidUnit(err.to_string()))
+        };
+
+        let (kind, max_fee, priority_fee, gas_price, expected_price, max_price) = match tx {
+            TypedTransaction::Eip1559(request) => {
+                let max_fee = request.max_fee_per_gas.unwrap_or_default();
+                let priority_fee = request.max_priority_fee_per_gas.unwrap_or_default();
+                let expected = max_fee.min(fees.base_fee + priority_fee);
+                (
+                    "eip1559",
+                    Some(gwei(max_fee)?),
+                    Some(gwei(priority_fee)?),
+                    None,
+                    expected,
+                    max_fee,
+                )
+            }
+            _ => {
+                let price = tx.gas_price().unwrap_or_default();
+                let kind = match tx {
+                    TypedTransaction::Eip2930(_) => "eip2930",
+                    _ => "legacy",
+                };
+                (kind, None, None, Some(gwei(price)?), price, price)
+            }
+        };
+
+        Ok(FeePreview {
+            kind: kind.to_string(),
+            from: tx
+                .from()
+                .map(|from| format!("{from:?}"))
+                .unwrap_or_default(),
+            to: tx.to_addr().map(|to| format!("{to:?}")),
+            value_ether: format_ether(value),
+            gas_limit: gas.to_string(),
+            base_fee_gwei: gwei(fees.base_fee)?,
+            max_fee_per_gas_gwei: max_fee,
+            max_priority_fee_per_gas_gwei: priority_fee,
+            gas_price_gwei: gas_price,
+            access_list_entries: tx.access_list().map_or(0, |list| list.0.len()),
+            expected_cost_ether: format_ether(value + gas * expected_price),
+            max_cost_ether: format_ether(value + gas * max_price),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::parse_value;
+    use ethers::types::Address;
+
+    fn gwei(amount: u64) -> U256 {
+        U256::from(amount) * 1_000_000_000u64
+    }
+
+    fn history(base_fees: &[u64], rewards: &[u64]) -> FeeHistory {
+        FeeHistory {
+            base_fee_per_gas: base_fees.iter().map(|fee| gwei(*fee)).collect(),
+            gas_used_ratio: vec![0.5; rewards.len()],
+            oldest_block: U256::from(100),
+            reward: rewards.iter().map(|reward| vec![gwei(*reward)]).collect(),
+        }
+    }
+
+    #[test]
+    fn estimates_fees_from_history() {
+        // base fees, rewards, expected base fee, tip and max fee in gwei
+        let cases = [
+            (vec![10, 11, 12], vec![3, 0, 1, 2], 12, 2, 26),
+            (vec![10, 11, 12], vec![5, 1], 12, 5, 29),
+            (vec![7], vec![0, 0], 7, 1, 15),
+            (vec![], vec![], 0, 1, 1),
+        ];
+
+        for (base_fees, rewards, base_fee, tip, max_fee) in cases {
+            let estimate = FeeEstimate::from_history(&history(&base_fees, &rewards), gwei(20));
+            assert_eq!(estimate.base_fee, gwei(base_fee), "{rewards:?}");
+            assert_eq!(estimate.max_priority_fee_per_gas, gwei(tip), "{rewards:?}");
+            assert_eq!(estimate.max_fee_per_gas, gwei(max_fee), "{rewards:?}");
+            assert_eq!(estimate.gas_price, gwei(20));
+        }
+    }
+
+    #[test]
+    fn previews_the_cost() {
+        let fees = FeeEstimate::from_history(&history(&[12], &[2]), gwei(20));
+        let request = TransactionRequest::new()
+            .from(Address::repeat_byte(1))
+            .to(Address::repeat_byte(2))
+            .value(parse_value("0.25", "ether").unwrap());
+
+        let mut tx = TxKind::Eip1559.build(request.clone());
+        tx.set_gas(21_000);
+        fees.apply(&mut tx);
+        let preview = FeePreview::new(&tx, &fees).unwrap();
+        assert_eq!(preview.kind, "eip1559");
+        assert_eq!(preview.value_ether, "0.250000000000000000");
+        assert_eq!(preview.base_fee_gwei, "12.000000000");
+        assert_eq!(
+            preview.max_fee_per_gas_gwei.as_deref(),
+            Some("26.000000000")
+        );
+        assert_eq!(
+            preview.max_priority_fee_per_gas_gwei.as_deref(),
+            Some("2.000000000")
+        );
+        assert_eq!(preview.gas_price_gwei, None);
+        // 21000 gas at the base fee plus tip of 14 gwei, at most 26 gwei.
+        assert_eq!(preview.expected_cost_ether, "0.250294000000000000");
+        assert_eq!(preview.max_cost_ether, "0.250546000000000000");
+
+        let mut tx = TxKind::Legacy.build(request);
+        tx.set_gas(21_000);
+        fees.apply(&mut tx);
+        let preview = FeePreview::new(&tx, &fees).unwrap();
+        assert_eq!(preview.kind, "legacy");
+        assert_eq!(preview.gas_price_gwei.as_deref(), Some("20.000000000"));
+        assert_eq!(preview.expected_cost_ether, "0.250420000000000000");
+        assert_eq!(preview.max_cost_ether, preview.expected_cost_ether);
+    }
+
+    #[test]
+    fn parses_amounts_and_units() {
+        let cases = [
+            (
+                "0.25",
+                "ether",
+                Some(U256::from(250_000_000_000_000_000u64)),
+            ),
+            ("1", "ether", Some(U256::exp10(18))),
+            ("1.5", "gwei", Some(U256::from(1_500_000_000u64))),
+            ("42", "wei", Some(U256::from(42))),
+            ("0.25", "furlong", None),
+            ("a quarter", "ether", None),
+        ];
+
+        for (amount, unit, expected) in cases {
+            assert_eq!(parse_value(amount, unit).ok(), expected, "{amount} {unit}");
+        }
+
+        assert_eq!("legacy".parse::<TxKind>(), Ok(TxKind::Legacy));
+        assert_eq!("2930".parse::<TxKind>(), Ok(TxKind::Eip2930));
+        assert_eq!("2".parse::<TxKind>(), Ok(TxKind::Eip1559));
+        assert!("4844".parse::<TxKind>().is_err());
+    }
+}
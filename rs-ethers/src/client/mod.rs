@@ -1,20 +1,41 @@
 use ethers::{
     middleware::{MiddlewareBuilder, SignerMiddleware},
     prelude::*,
-    utils::{parse_units, AnvilInstance, ParseUnits},
+    types::transaction::{eip2718::TypedTransaction, eip712::TypedData},
+    utils::{parse_units, ParseUnits},
 };
+use fees::{FeeEstimate, FEE_HISTORY_BLOCKS, REWARD_PERCENTILE};
 use gas_oracle::ProviderOracle;
-use std::error::Error;
+mod anvil;
+mod batch;
+mod error;
+mod fees;
+mod signer;
+mod transport;
+
+pub use anvil::{AnvilConfig, AnvilSession, LocalNode};
+pub use batch::CallOutput;
+pub use error::ClientError;
+pub use fees::{FeePreview, TxKind};
+pub use signer::{create_keystore, SignerSource, DEFAULT_DERIVATION_PATH};
+pub use transport::Transport;
 
 pub struct EtherClient {
-    provider: Provider<Http>,
-    client: Option<SignerMiddleware<Provider<Http>, LocalWallet>>,
+    provider: Provider<Transport>,
+    client: Option<SignerMiddleware<Provider<Transport>, LocalWallet>>,
     address: Option<H160>,
 }
 
 impl EtherClient {
-    pub fn new(rpc: String) -> Result<EtherClient, Box<dyn Error>> {
-        let provider: Provider<Http> = Provider::<Http>::try_from(rpc)?;
+    pub async fn new(rpc: String) -> Result<EtherClient, ClientError> {
+        let transport =
+            Transport::connect(&rpc)
+                .await
+                .map_err(|err| ClientError::NotConnected {
+                    end_point: rpc.clone(),
+                    reason: err.to_string(),
+                })?;
+        let provider: Provider<Transport> = Provider::new(transport);
 
         Ok(Self {
             provider,
@@ -23,162 +44,490 @@ impl EtherClient {
         })
     }
 
-    pub fn set_client_with_privet_key(
+    pub fn set_client_with_private_key(
         &mut self,
         wallet: LocalWallet,
         chain_id: u64,
-    ) -> Result<(), Box<dyn Error>> {
-        let value: Option<SignerMiddleware<Provider<Http>, LocalWallet>> = Some(
-            SignerMiddleware::new(self.provider.clone(), wallet.with_chain_id(chain_id)),
-        );
+    ) -> Result<(), ClientError> {
+        let client: SignerMiddleware<Provider<Transport>, LocalWallet> =
+            SignerMiddleware::new(self.provider.clone(), wallet.with_chain_id(chain_id));
 
-        (self.client, self.address) = match value {
-            Some(value) => (Some(value.clone()), Some(value.address())),
-            None => {
-                return Err(Box::new(std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    "Intialization failed",
-                )))
-            }
-        };
+        self.address = Some(client.address());
+        self.client = Some(client);
 
         Ok(())
     }
 
     pub fn load_wallet(
         &self,
-        instance: Option<&AnvilInstance>,
-        p_key: String,
-    ) -> Result<LocalWallet, Box<dyn Error>> {
-        if instance.is_none() && p_key.is_empty() {
-            return Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                "Privet key not found for provided instance",
-            )));
-        }
-        let wallet: LocalWallet = match instance {
-            Some(instance) => instance.keys()[0].clone().into(),
-            None => p_key.parse::<LocalWallet>()?,
-        };
-
-        Ok(wallet)
+        node: Option<&LocalNode>,
+        source: &SignerSource,
+    ) -> Result<LocalWallet, ClientError> {
+        source.load(node)
     }
 
+    /// Builds a plain transfer, `value` may be a decimal amount such as `0.25` ether.
     pub fn create_raw_coin_tx(
         &self,
-        to: &str,
-        value: u64,
+        to: Address,
+        value: &str,
         to_unit: &str,
-    ) -> Result<TransactionRequest, Box<dyn Error>> {
-        let user_account = self.address.unwrap();
-
-        let value: ParseUnits = match to_unit {
-            "wei" => parse_units(value, "wei").unwrap(),
-            "kwei" => parse_units(value, "kwei").unwrap(),
-            "mwei" => parse_units(value, "mwei").unwrap(),
-            "gwei" => parse_units(value, "gwei").unwrap(),
-            "szabo" => parse_units(value, "szabo").unwrap(),
-            "finney" => parse_units(value, "finney").unwrap(),
-            "ether" => parse_units(value, "ether").unwrap(),
-            _ => value.into(),
-        };
+        kind: TxKind,
+    ) -> Result<TypedTransaction, ClientError> {
+        let value = parse_value(value, to_unit)?;
+        let tx = TransactionRequest::new().to(to).value(value);
 
-        let value = U256::from(value);
+        Ok(kind.build(self.with_sender(tx)))
+    }
 
-        let to: H160 = to.parse().unwrap();
-        let tx = TransactionRequest::new()
-            .from(user_account)
-            .to(to)
-            .value(value);
+    /// Contract call `request` from the loaded signer, `value` in ether.
+    pub fn create_contract_tx(
+        &self,
+        request: TransactionRequest,
+        value: &str,
+        kind: TxKind,
+    ) -> Result<TypedTransaction, ClientError> {
+        let tx = request.value(parse_value(value, "ether")?);
 
-        Ok(tx)
+        Ok(kind.build(self.with_sender(tx)))
     }
 
-    pub fn get_client(&self) -> Option<SignerMiddleware<Provider<Http>, LocalWallet>> {
-        self.client.clone()
+    /// Sets the loaded signer as sender, without one `from` is left to the caller.
+    fn with_sender(&self, tx: TransactionRequest) -> TransactionRequest {
+        match self.address {
+            Some(address) => tx.from(address),
+            None => tx,
+        }
     }
 
-    pub async fn send_raw_tx(&self, tx: TransactionRequest) -> Result<(), Box<dyn Error>> {
-        let user_account = self.address.unwrap();
-        let nonce_manager = self.client.clone().unwrap().nonce_manager(user_account);
-
-        let pending_tx = nonce_manager.send_transaction(tx, None).await?.await?;
+    pub async fn estimate_fees(&self) -> Result<FeeEstimate, ClientError> {
+        let history = self
+            .provider
+            .fee_history(
+                FEE_HISTORY_BLOCKS,
+                BlockNumber::Latest,
+                &[REWARD_PERCENTILE],
+            )
+            .await?;
+        let gas_price = self.provider.get_gas_price().await?;
+
+        Ok(FeeEstimate::from_history(&history, gas_price))
+    }
 
-        if pending_tx.is_some() {
-            println!(
-                "Pending tx: {}",
-                serde_json::to_string_pretty(&pending_tx.unwrap())?
-            );
+    /// Fills access list, gas limit and fees of `tx` and returns what it will cost.
+    pub async fn prepare_tx(
+        &self,
+        tx: &mut TypedTransaction,
+        with_access_list: bool,
+    ) -> Result<FeePreview, ClientError> {
+        if with_access_list {
+            if let TypedTransaction::Legacy(_) = tx {
+                return Err(ClientError::Transaction(
+                    "legacy transactions cannot carry an access list".to_string(),
+                ));
+            }
+            let access_list = self.provider.create_access_list(tx, None).await?;
+            tx.set_access_list(access_list.access_list);
         }
 
-        Ok(())
+        let gas = self.provider.estimate_gas(tx, None).await?;
+        tx.set_gas(gas);
+
+        let fees = self.estimate_fees().await?;
+        fees.apply(tx);
+
+        FeePreview::new(tx, &fees)
+    }
+
+    pub fn get_provider(&self) -> Provider<Transport> {
+        self.provider.clone()
+    }
+
+    pub fn get_wallet(&self) -> Option<LocalWallet> {
+        self.client.as_ref().map(|client| client.signer().clone())
+    }
+
+    pub async fn send_raw_tx(
+        &self,
+        tx: TypedTransaction,
+    ) -> Result<Option<TransactionReceipt>, ClientError> {
+        let client = self.client.clone().ok_or(ClientError::NoSigner)?;
+        let nonce_manager = client.nonce_manager(self.address.ok_or(ClientError::NoSigner)?);
+
+        let pending = nonce_manager
+            .send_transaction(tx, None)
+            .await
+            .map_err(|err| ClientError::Transaction(err.to_string()))?;
+        let receipt = pending.await?;
+
+        Ok(receipt)
     }
 
-    pub async fn get_block_count(&self) -> Result<U64, Box<dyn Error>> {
+    pub async fn get_block_count(&self) -> Result<U64, ClientError> {
         Ok(self.provider.get_block_number().await?)
     }
 
-    pub async fn get_chain_id(&self) -> Result<U256, Box<dyn Error>> {
+    pub async fn get_chain_id(&self) -> Result<U256, ClientError> {
         Ok(self.provider.get_chainid().await?)
     }
 
-    pub async fn get_tx_pool(&self) -> Result<TxpoolContent, Box<dyn Error>> {
+    pub async fn get_tx_pool(&self) -> Result<TxpoolContent, ClientError> {
         Ok(self.provider.txpool_content().await?)
     }
 
-    pub async fn get_block(&self, block_number: u64) -> Result<Block<H256>, Box<dyn Error>> {
-        let block = self.provider.get_block(block_number).await?;
-
-        let result = match block {
-            Some(value) => Ok(value),
-            None => Err(()),
-        };
-
-        Ok(result.unwrap())
+    /// `None` when the node does not know the block yet.
+    pub async fn get_block(&self, block_number: u64) -> Result<Option<Block<H256>>, ClientError> {
+        Ok(self.provider.get_block(block_number).await?)
     }
 
-    pub async fn get_account_balance(&self, from: Address) -> Result<U256, Box<dyn Error>> {
+    pub async fn get_account_balance(&self, from: Address) -> Result<U256, ClientError> {
         Ok(self.provider.get_balance(from, None).await?)
     }
 
-    pub async fn get_gas_price_oracle(&self) -> Result<U256, Box<dyn Error>> {
+    /// Nonce of the next transaction `from` gets mined, pool transactions not counted.
+    pub async fn get_transaction_count(&self, from: Address) -> Result<U256, ClientError> {
+        Ok(self
+            .provider
+            .get_transaction_count(from, Some(BlockNumber::Latest.into()))
+            .await?)
+    }
+
+    pub async fn get_gas_price_oracle(&self) -> Result<U256, ClientError> {
         let oracle = ProviderOracle::new(self.provider.clone());
 
-        let price: U256 = oracle.fetch().await?;
+        let price: U256 = oracle
+            .fetch()
+            .await
+            .map_err(|err| ProviderError::CustomError(err.to_string()))?;
         Ok(price)
     }
 
-    pub async fn get_code(&self, at: Address) -> Result<Bytes, Box<dyn Error>> {
+    pub async fn get_code(&self, at: Address) -> Result<Bytes, ClientError> {
         Ok(self.provider.get_code(at, None).await?)
     }
 
-    pub async fn is_contract_exists(&self, at: Address) -> Result<bool, Box<dyn Error>> {
+    pub async fn is_contract_exists(&self, at: Address) -> Result<bool, ClientError> {
         let code = self.get_code(at).await?;
 
-        Ok(code.len() > 0)
+        Ok(!code.is_empty())
     }
 
-    pub async fn get_slot_data(&self, at: Address, slot: TxHash) -> Result<H256, Box<dyn Error>> {
+    /// ENS forward resolution through the registry at its canonical address.
+    pub async fn resolve_name(&self, name: &str) -> Result<Address, ClientError> {
+        self.ensure_ens().await?;
+        self.provider
+            .resolve_name(name)
+            .await
+            .map_err(|err| ClientError::Ens(format!("{name}: {err}")))
+    }
+
+    /// ENS reverse record of `address`, checked against the forward resolution.
+    pub async fn lookup_address(&self, address: Address) -> Result<String, ClientError> {
+        self.ensure_ens().await?;
+        self.provider
+            .lookup_address(address)
+            .await
+            .map_err(|err| ClientError::Ens(format!("{address:?}: {err}")))
+    }
+
+    async fn ensure_ens(&self) -> Result<(), ClientError> {
+        match self.is_contract_exists(ENS_ADDRESS).await? {
+            true => Ok(()),
+            false => Err(ClientError::Ens(
+                "no ENS registry deployed on this chain".to_string(),
+            )),
+        }
+    }
+
+    pub async fn get_slot_data(&self, at: Address, slot: TxHash) -> Result<H256, ClientError> {
         let slot_data = self.provider.get_storage_at(at, slot, None).await?;
 
         Ok(slot_data)
     }
 
+    /// Logs matching `filter` from block `from` up to `chunk` blocks, but never past `to`.
+    /// A failed request is retried with half the range since providers cap the
+    /// blocks or results per `eth_getLogs`, `chunk` keeps the size that worked.
+    /// Returns the logs and the last block they cover.
+    pub async fn get_logs_chunk(
+        &self,
+        filter: &Filter,
+        from: u64,
+        to: u64,
+        chunk: &mut u64,
+    ) -> Result<(Vec<Log>, u64), ClientError> {
+        loop {
+            let end = to.min(from + (*chunk).max(1) - 1);
+            let range = filter.clone().from_block(from).to_block(end);
+
+            match self.provider.get_logs(&range).await {
+                Ok(logs) => return Ok((logs, end)),
+                Err(_) if end > from => *chunk = (end - from).div_ceil(2),
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+
+    /// `eth_call` of a full transaction, returns the raw output.
+    pub async fn call_tx(
+        &self,
+        tx: &TypedTransaction,
+        block: Option<BlockId>,
+    ) -> Result<Bytes, ClientError> {
+        Ok(self.provider.call(tx, block).await?)
+    }
+
+    pub async fn estimate_gas(
+        &self,
+        tx: &TypedTransaction,
+        block: Option<BlockId>,
+    ) -> Result<U256, ClientError> {
+        Ok(self.provider.estimate_gas(tx, block).await?)
+    }
+
+    /// Call tree of `tx` with the logs of every frame, from the `callTracer`.
+    pub async fn trace_call(
+        &self,
+        tx: &TypedTransaction,
+        block: Option<BlockId>,
+    ) -> Result<CallFrame, ClientError> {
+        let config = GethDebugBuiltInTracerConfig::CallTracer(CallConfig {
+            only_top_call: Some(false),
+            with_log: Some(true),
+        });
+
+        match self
+            .debug_trace_call(tx, block, GethDebugBuiltInTracerType::CallTracer, config)
+            .await?
+        {
+            GethTrace::Known(GethTraceFrame::CallTracer(frame)) => Ok(frame),
+            trace => Err(unexpected_trace(trace)),
+        }
+    }
+
+    /// Accounts touched by `tx` before and after it ran, from the `prestateTracer` in diff mode.
+    pub async fn trace_state_diff(
+        &self,
+        tx: &TypedTransaction,
+        block: Option<BlockId>,
+    ) -> Result<DiffMode, ClientError> {
+        let config = GethDebugBuiltInTracerConfig::PreStateTracer(PreStateConfig {
+            diff_mode: Some(true),
+        });
+
+        match self
+            .debug_trace_call(
+                tx,
+                block,
+                GethDebugBuiltInTracerType::PreStateTracer,
+                config,
+            )
+            .await?
+        {
+            GethTrace::Known(GethTraceFrame::PreStateTracer(PreStateFrame::Diff(diff))) => Ok(diff),
+            trace => Err(unexpected_trace(trace)),
+        }
+    }
+
+    async fn debug_trace_call(
+        &self,
+        tx: &TypedTransaction,
+        block: Option<BlockId>,
+        tracer: GethDebugBuiltInTracerType,
+        config: GethDebugBuiltInTracerConfig,
+    ) -> Result<GethTrace, ClientError> {
+        let options = GethDebugTracingCallOptions {
+            tracing_options: GethDebugTracingOptions {
+                tracer: Some(GethDebugTracerType::BuiltInTracer(tracer)),
+                tracer_config: Some(GethDebugTracerConfig::BuiltInTracer(config)),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        Ok(self
+            .provider
+            .debug_trace_call(tx.clone(), block, options)
+            .await?)
+    }
+
     pub async fn get_transaction_data(
         &self,
         transaction_hash: TxHash,
-    ) -> Result<Option<Transaction>, Box<dyn Error>> {
+    ) -> Result<Option<Transaction>, ClientError> {
         let transaction = self.provider.get_transaction(transaction_hash).await?;
 
         Ok(transaction)
     }
 
+    pub async fn subscribe_new_heads(
+        &self,
+    ) -> Result<SubscriptionStream<'_, Transport, Block<TxHash>>, ClientError> {
+        self.ensure_pubsub()?;
+        Ok(self.provider.subscribe_blocks().await?)
+    }
+
+    pub async fn subscribe_pending_txs(
+        &self,
+    ) -> Result<SubscriptionStream<'_, Transport, TxHash>, ClientError> {
+        self.ensure_pubsub()?;
+        Ok(self.provider.subscribe_pending_txs().await?)
+    }
+
+    pub async fn subscribe_logs(
+        &self,
+        filter: &Filter,
+    ) -> Result<SubscriptionStream<'_, Transport, Log>, ClientError> {
+        self.ensure_pubsub()?;
+        Ok(self.provider.subscribe_logs(filter).await?)
+    }
+
+    fn ensure_pubsub(&self) -> Result<(), ClientError> {
+        if !self.provider.as_ref().supports_pubsub() {
+            return Err(ClientError::PubsubUnsupported);
+        }
+        Ok(())
+    }
+
+    /// EIP-191 `personal_sign` signature over `message`.
     pub async fn sign_message(
         &self,
-        message: String,
+        message: &[u8],
+        wallet: LocalWallet,
+    ) -> Result<Signature, ClientError> {
+        let signature = wallet.sign_message(message).await?;
+        Ok(signature)
+    }
+
+    /// EIP-712 signature over the hash of `data` and its domain.
+    pub async fn sign_typed_data(
+        &self,
+        data: &TypedData,
         wallet: LocalWallet,
-    ) -> Result<Signature, Box<dyn Error>> {
-        let signature = wallet.sign_message(message.as_bytes()).await?;
+    ) -> Result<Signature, ClientError> {
+        let signature = wallet.sign_typed_data(data).await?;
         Ok(signature)
     }
 }
+
+fn parse_value(value: &str, unit: &str) -> Result<U256, ClientError> {
+    let value: ParseUnits = parse_units(value, unit)
+        .map_err(|err| ClientError::InvalidUnit(format!("{value} {unit}: {err}")))?;
+    Ok(U256::from(value))
+}
+
+fn unexpected_trace(trace: GethTrace) -> ClientError {
+    let trace = serde_json::to_string(&trace).unwrap_or_default();
+    ProviderError::CustomError(format!("unexpected trace from the node: {trace}")).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        sync::{Arc, Mutex},
+        thread,
+    };
+
+    /// Widest range the fake node answers `eth_getLogs` for, like a capped provider.
+    const MAX_LOG_RANGE: u64 = 250;
+
+    /// `(from, to)` of every `eth_getLogs` the fake node received.
+    type Ranges = Arc<Mutex<Vec<(u64, u64)>>>;
+
+    /// HTTP JSON-RPC node that records the requested log ranges and refuses wide ones.
+    fn capped_node() -> (String, Ranges) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let end_point = format!("http://{}", listener.local_addr().unwrap());
+        let ranges = Arc::new(Mutex::new(Vec::new()));
+
+        let seen = ranges.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                loop {
+                    let mut length = 0;
+                    let mut line = String::new();
+                    while reader.read_line(&mut line).unwrap_or(0) > 2 {
+                        if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                            length = value.trim().parse().unwrap();
+                        }
+                        line.clear();
+                    }
+                    if line.is_empty() {
+                        break;
+                    }
+
+                    let mut body = vec![0; length];
+                    reader.read_exact(&mut body).unwrap();
+                    let request: Value = serde_json::from_slice(&body).unwrap();
+                    let block = |key: &str| {
+                        let number = request["params"][0][key].as_str().unwrap();
+                        u64::from_str_radix(number.trim_start_matches("0x"), 16).unwrap()
+                    };
+                    let (from, to) = (block("fromBlock"), block("toBlock"));
+                    seen.lock().unwrap().push((from, to));
+
+                    let response = match to - from < MAX_LOG_RANGE {
+                        true => json!({ "jsonrpc": "2.0", "id": request["id"], "result": [] }),
+                        false => json!({
+                            "jsonrpc": "2.0",
+                            "id": request["id"],
+                            "error": { "code": -32005, "message": "query exceeds max block range" },
+                        }),
+                    }
+                    .to_string();
+                    write!(
+                        stream,
+                        "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{response}",
+                        response.len()
+                    )
+                    .unwrap();
+                }
+            }
+        });
+        (end_point, ranges)
+    }
+
+    #[tokio::test]
+    async fn splits_log_ranges_the_node_refuses() {
+        let (end_point, ranges) = capped_node();
+        let client = EtherClient::new(end_point).await.unwrap();
+        let filter = Filter::new();
+
+        let mut chunk = 1_000;
+        let mut next = 0;
+        while next <= 999 {
+            let (_, end) = client
+                .get_logs_chunk(&filter, next, 999, &mut chunk)
+                .await
+                .unwrap();
+            next = end + 1;
+        }
+
+        // Halved until the node answers, then that size is kept.
+        assert_eq!(chunk, 250);
+        assert_eq!(
+            *ranges.lock().unwrap(),
+            [
+                (0, 999),
+                (0, 499),
+                (0, 249),
+                (250, 499),
+                (500, 749),
+                (750, 999)
+            ]
+        );
+
+        // Never past `to`.
+        let (_, end) = client
+            .get_logs_chunk(&filter, 990, 995, &mut chunk)
+            .await
+            .unwrap();
+        assert_eq!(end, 995);
+    }
+}
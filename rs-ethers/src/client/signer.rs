@@ -0,0 +1,75 @@
+use super::{ClientError, LocalNode};
+use ethers::{
+    core::rand::thread_rng,
+    signers::{coins_bip39::English, LocalWallet, MnemonicBuilder},
+};
+use std::path::{Path, PathBuf};
+
+/// Account prefix of the standard Ethereum derivation path, the index is appended.
+pub const DEFAULT_DERIVATION_PATH: &str = "m/44'/60'/0'/0";
+
+/// Where the signing key comes from.
+pub enum SignerSource {
+    /// First dev account of the local Anvil node.
+    Anvil,
+    /// Raw hex private key.
+    PrivateKey(String),
+    /// Encrypted JSON keystore (Web3 Secret Storage).
+    Keystore { path: PathBuf, password: String },
+    /// BIP39 phrase, `path` is the derivation prefix the index is appended to.
+    Mnemonic {
+        phrase: String,
+        path: String,
+        index: u32,
+    },
+}
+
+impl SignerSource {
+    pub fn load(&self, node: Option<&LocalNode>) -> Result<LocalWallet, ClientError> {
+        let wallet = match self {
+            SignerSource::Anvil => match node {
+                Some(node) => node.dev_wallet()?,
+                None => return Err(ClientError::NoSigner),
+            },
+            SignerSource::PrivateKey(key) => key.trim().parse::<LocalWallet>()?,
+            SignerSource::Keystore { path, password } => {
+                LocalWallet::decrypt_keystore(path, password)?
+            }
+            SignerSource::Mnemonic {
+                phrase,
+                path,
+                index,
+            } => MnemonicBuilder::<English>::default()
+                .phrase(phrase.trim())
+                .derivation_path(&format!("{}/{}", path.trim_end_matches('/'), index))?
+                .build()?,
+        };
+
+        Ok(wallet)
+    }
+}
+
+/// Writes a keystore for a fresh random key, or for `private_key` when given,
+/// into `dir` and returns the wallet with the keystore file path.
+pub fn create_keystore(
+    dir: &Path,
+    password: &str,
+    private_key: Option<&str>,
+    name: Option<&str>,
+) -> Result<(LocalWallet, PathBuf), ClientError> {
+    std::fs::create_dir_all(dir).map_err(|err| ClientError::Signer(err.into()))?;
+
+    let mut rng = thread_rng();
+    let (wallet, uuid) = match private_key {
+        Some(key) => {
+            let key = key.trim().trim_start_matches("0x");
+            let bytes =
+                ethers::utils::hex::decode(key).map_err(|err| ClientError::Signer(err.into()))?;
+            LocalWallet::encrypt_keystore(dir, &mut rng, bytes, password, name)?
+        }
+        None => LocalWallet::new_keystore(dir, &mut rng, password, name)?,
+    };
+
+    // eth-keystore names the file after the uuid unless a name was given.
+    Ok((wallet, dir.join(name.unwrap_or(&uuid))))
+}
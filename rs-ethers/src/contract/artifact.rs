@@ -0,0 +1,188 @@
+use super::{load_abi, tokenize};
+use ethers::{
+    abi::Abi,
+    solc::{
+        artifacts::{output_selection::ContractOutputSelection, CompilerOutput, Contract},
+        CompilerInput, Solc,
+    },
+    types::Bytes,
+};
+use eyre::{eyre, Ok, Result};
+use serde_json::{json, Value};
+use std::{fs, path::Path};
+
+/// A compiled contract ready to deploy, from solc or a Foundry/Hardhat artifact.
+pub struct Artifact {
+    pub name: String,
+    pub abi: Abi,
+    pub bytecode: Bytes,
+    /// Compiler version, language and settings when the source provides them.
+    pub metadata: Value,
+}
+
+impl Artifact {
+    /// Compiles `.sol` files, anything else is read as an artifact JSON.
+    /// `name` picks the contract when the source defines more than one
+    /// deployable contract, imports and interfaces are not counted.
+    pub fn load(path: &str, name: Option<&str>) -> Result<Artifact> {
+        match path.ends_with(".sol") {
+            true => Self::compile(path, name),
+            false => Self::from_json(path, name),
+        }
+    }
+
+    fn compile(path: &str, name: Option<&str>) -> Result<Artifact> {
+        let solc = Solc::default();
+        // The default output selection leaves out the metadata, which holds the
+        // settings (optimizer, runs, evmVersion, remappings) a verifier needs.
+        let mut output = CompilerOutput::default();
+        for mut input in CompilerInput::new(path)? {
+            input
+                .settings
+                .push_output_selection(ContractOutputSelection::Metadata);
+            output.merge(solc.compile(&input)?);
+        }
+        if output.has_error() {
+            let errors: Vec<String> = output
+                .errors
+                .iter()
+                .filter(|err| err.severity.is_error())
+                .map(|err| err.formatted_message.clone().unwrap_or(err.message.clone()))
+                .collect();
+            return Err(eyre!("solc failed:\n{}", errors.join("\n")));
+        }
+
+        let contracts: Vec<(&String, &Contract)> = match name {
+            Some(name) => output
+                .contracts_iter()
+                .filter(|(contract_name, _)| *contract_name == name)
+                .collect(),
+            None => output
+                .contracts
+                .iter()
+                .filter(|(file, _)| Path::new(file) == Path::new(path))
+                .flat_map(|(_, contracts)| contracts.iter())
+                .filter(|(_, contract)| is_deployable(contract))
+                .collect(),
+        };
+        let (contract_name, contract) = match contracts.as_slice() {
+            [contract] => *contract,
+            [] => match name {
+                Some(name) => return Err(eyre!("no contract {name} in {path}")),
+                None => return Err(eyre!("solc produced no contracts for {path}")),
+            },
+            _ => {
+                return Err(eyre!(
+                    "{path} defines several contracts, pick one with --contract"
+                ))
+            }
+        };
+
+        let abi = contract
+            .abi
+            .as_ref()
+            .ok_or_else(|| eyre!("solc returned no ABI for {contract_name}"))?
+            .abi
+            .clone();
+        let bytecode = contract
+            .evm
+            .as_ref()
+            .and_then(|evm| evm.bytecode.as_ref())
+            .ok_or_else(|| eyre!("solc returned no bytecode for {contract_name}"))?;
+        let bytecode = bytecode
+            .object
+            .as_bytes()
+            .ok_or_else(|| eyre!("{contract_name} needs library linking"))?
+            .clone();
+
+        let metadata = match &contract.metadata {
+            Some(metadata) => verification_metadata(serde_json::from_str(&metadata.raw_metadata)?),
+            None => json!({ "compiler": { "version": solc.version()?.to_string() } }),
+        };
+
+        Ok(Artifact {
+            name: contract_name.clone(),
+            abi,
+            bytecode,
+            metadata,
+        })
+    }
+
+    fn from_json(path: &str, name: Option<&str>) -> Result<Artifact> {
+        let json: Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+
+        // Foundry nests the creation code under `bytecode.object`, Hardhat does not.
+        let bytecode = match &json["bytecode"] {
+            Value::String(code) => code.as_str(),
+            code => code["object"].as_str().unwrap_or_default(),
+        };
+        if bytecode.contains("__") {
+            return Err(eyre!("{path} needs library linking"));
+        }
+        let bytecode: Bytes = bytecode.parse()?;
+        if bytecode.is_empty() {
+            return Err(eyre!("{path} has no creation bytecode"));
+        }
+
+        let name = match (name, json["contractName"].as_str()) {
+            (Some(name), _) | (None, Some(name)) => name.to_string(),
+            (None, None) => Path::new(path)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default(),
+        };
+
+        // Foundry keeps the solc metadata, either parsed or as the raw string.
+        let metadata = match (&json["metadata"], &json["rawMetadata"]) {
+            (Value::Object(_), _) => verification_metadata(json["metadata"].clone()),
+            (Value::String(raw), _) | (_, Value::String(raw)) => {
+                verification_metadata(serde_json::from_str(raw)?)
+            }
+            _ => Value::Null,
+        };
+
+        Ok(Artifact {
+            name,
+            abi: load_abi(path)?,
+            bytecode,
+            metadata,
+        })
+    }
+
+    /// Creation code followed by the ABI encoded constructor arguments.
+    pub fn deploy_code(&self, args: &[String]) -> Result<Bytes> {
+        let Some(constructor) = self.abi.constructor() else {
+            if !args.is_empty() {
+                return Err(eyre!("{} has no constructor arguments", self.name));
+            }
+            return Ok(self.bytecode.clone());
+        };
+
+        let tokens = tokenize(&constructor.inputs, args, "constructor")?;
+        Ok(constructor
+            .encode_input(self.bytecode.to_vec(), &tokens)?
+            .into())
+    }
+}
+
+/// Interfaces and abstract contracts compile to empty creation code.
+fn is_deployable(contract: &Contract) -> bool {
+    contract
+        .evm
+        .as_ref()
+        .and_then(|evm| evm.bytecode.as_ref())
+        .is_some_and(|bytecode| match bytecode.object.as_bytes() {
+            Some(bytes) => !bytes.is_empty(),
+            // Unlinked code still deploys once its libraries are linked.
+            None => true,
+        })
+}
+
+/// The parts of solc metadata a block explorer asks for when verifying.
+fn verification_metadata(metadata: Value) -> Value {
+    json!({
+        "compiler": metadata["compiler"],
+        "language": metadata["language"],
+        "settings": metadata["settings"],
+    })
+}
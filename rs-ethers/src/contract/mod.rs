@@ -1,94 +1,323 @@
-use abi::Abi;
+use abi::{
+    parse_abi,
+    token::{LenientTokenizer, Tokenizer},
+    Abi, AbiDecode, Event, Function, Param, ParamType, RawLog, Token,
+};
 use ethers::contract::Contract;
-use ethers::{prelude::*, solc::Solc};
-use eyre::{ErrReport, Ok, Result};
+use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::utils::{hex, to_checksum};
+use eyre::{eyre, Ok, Result};
+use serde_json::{json, Map, Value};
 use std::fs;
 use std::sync::Arc;
 
-const RPC: &str = "https://eth.llamarpc.com";
+mod artifact;
+mod storage;
+mod token;
 
-pub struct Contracts {
+pub use artifact::Artifact;
+pub use storage::{StorageLayout, StorageReader};
+pub use token::{Erc20, Erc721, IERC20_ABI};
+
+/// Selector of `Error(string)` reverts.
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+/// Selector of `Panic(uint256)` reverts.
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+pub struct Contracts<M> {
     abi: Abi,
     address: Address,
-    contract: Contract<SignerMiddleware<Provider<Http>, LocalWallet>>,
+    contract: Contract<M>,
 }
 
-impl Contracts {
-    pub fn new(
-        provider: SignerMiddleware<Provider<Http>, LocalWallet>,
+impl<M: Middleware> Contracts<M> {
+    pub fn new(provider: M, contract_address: &str, file_path: &str) -> Result<Contracts<M>> {
+        let abi = load_abi(file_path)?;
+        Self::with_abi(provider, contract_address, abi)
+    }
+
+    /// Contract with a single function parsed from a signature such as
+    /// `balanceOf(address)(uint256)` or `balanceOf(address) returns (uint256)`.
+    pub fn from_signature(
+        provider: M,
         contract_address: &str,
-        file_path: &str,
-    ) -> Result<Option<Contracts>, ErrReport> {
-        let provider = Arc::new(provider.clone());
-        let abi_json = fs::read_to_string(file_path)?;
-        let abi: Abi = serde_json::from_str(&abi_json)?;
+        signature: &str,
+    ) -> Result<Contracts<M>> {
+        let signature = match signature.split_once(")(") {
+            Some((inputs, outputs)) => format!("{inputs}) returns ({outputs}"),
+            None => signature.to_string(),
+        };
+        let abi = parse_abi(&[&format!("function {signature}")])?;
+        Self::with_abi(provider, contract_address, abi)
+    }
 
+    fn with_abi(provider: M, contract_address: &str, abi: Abi) -> Result<Contracts<M>> {
         let contract_address: Address = contract_address.parse()?;
+        let contract: Contract<M> =
+            Contract::new(contract_address, abi.clone(), Arc::new(provider));
 
-        let contract: Contract<SignerMiddleware<Provider<Http>, LocalWallet>> =
-            Contract::new(contract_address, abi.clone(), provider);
-
-        Ok(Some(Self {
+        Ok(Self {
             abi,
             address: contract_address,
             contract,
-        }))
-    }
-
-    pub fn compile_contract(
-        contract_name: &str,
-        sol_file_path: &str,
-        output_file_path: &str,
-    ) -> Result<(), ErrReport> {
-        println!("Generating bindings for {contract_name}\n");
-
-        let abi = if sol_file_path.ends_with(".sol") {
-            let contract = Solc::default().compile_source(&sol_file_path)?;
-            let abi = contract
-                .get(sol_file_path, contract_name)
-                .unwrap()
-                .abi
-                .unwrap();
-            Some(serde_json::to_string(abi).unwrap())
-        } else {
-            None
+        })
+    }
+
+    /// Finds a function by name, `name(types)` or full signature, the latter two
+    /// pick between overloads.
+    pub fn function(&self, name: &str) -> Result<&Function> {
+        let Some((short_name, _)) = name.split_once('(') else {
+            let overloads = self.abi.functions_by_name(name)?;
+            return match overloads.as_slice() {
+                [function] => Ok(function),
+                _ => Err(eyre!(
+                    "{name} is overloaded, use one of: {}",
+                    overloads
+                        .iter()
+                        .map(input_signature)
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                )),
+            };
         };
 
-        let binding = Abigen::new(contract_name, abi.unwrap())?.generate()?;
+        // Drop a trailing `(outputs)` or `returns (...)` part, tuples nest parentheses.
+        let mut depth = 0;
+        let end = name
+            .char_indices()
+            .find(|(_, c)| {
+                match c {
+                    '(' => depth += 1,
+                    ')' => depth -= 1,
+                    _ => return false,
+                }
+                depth == 0
+            })
+            .map_or(name.len(), |(index, _)| index + 1);
+        let wanted = name[..end].replace(' ', "");
+        self.abi
+            .functions_by_name(short_name)?
+            .iter()
+            .find(|function| input_signature(function) == wanted)
+            .ok_or_else(|| eyre!("no function {wanted} in the ABI"))
+    }
+
+    /// ABI encodes `args` for `function`, arrays as `[a,b]` and tuples as `(a,b)`.
+    pub fn encode(&self, function: &Function, args: &[String]) -> Result<Bytes> {
+        let tokens = tokenize(&function.inputs, args, &input_signature(function))?;
+        Ok(function.encode_input(&tokens)?.into())
+    }
+
+    /// Contract with a single event parsed from a signature such as
+    /// `Transfer(address indexed from, address indexed to, uint256 value)`.
+    pub fn from_event(
+        provider: M,
+        contract_address: &str,
+        signature: &str,
+    ) -> Result<Contracts<M>> {
+        let abi = parse_abi(&[&format!("event {signature}")])?;
+        Self::with_abi(provider, contract_address, abi)
+    }
+
+    /// Finds an event by name, or by a signature with or without parameter names
+    /// and `indexed`, which picks between overloads.
+    pub fn event(&self, name: &str) -> Result<&Event> {
+        let short_name = name.split('(').next().unwrap_or_default().trim();
+        let events = self.abi.events_by_name(short_name)?;
 
-        let output_file_path = output_file_path.to_owned();
+        if !name.contains('(') {
+            return match events.as_slice() {
+                [event] => Ok(event),
+                _ => Err(eyre!("{name} is overloaded, pass its signature")),
+            };
+        }
 
-        match output_file_path.is_empty() {
-            false => binding.write_to_file(output_file_path)?,
-            true => binding.write(&mut std::io::stdout())?,
+        let topic = parse_abi(&[&format!("event {name}")])?
+            .events()
+            .next()
+            .map(|event| event.signature());
+        events
+            .iter()
+            .find(|event| Some(event.signature()) == topic)
+            .ok_or_else(|| eyre!("no event {name} in the ABI"))
+    }
+
+    /// Decodes a log with the event from the ABI whose topic matches, `None`
+    /// for logs of unknown or anonymous events.
+    pub fn decode_log(&self, log: &Log) -> Option<(&Event, Map<String, Value>)> {
+        let topic = log.topics.first()?;
+        let event = self
+            .abi
+            .events()
+            .find(|event| !event.anonymous && event.signature() == *topic)?;
+
+        let raw = RawLog {
+            topics: log.topics.clone(),
+            data: log.data.to_vec(),
         };
+        let decoded = event.parse_log(raw).ok()?;
+        let fields = decoded
+            .params
+            .into_iter()
+            .map(|param| (param.name, token_to_json(param.value)))
+            .collect();
 
-        Ok(())
+        Some((event, fields))
     }
 
-    pub fn print_all_contract_functions(&self) {
-        for function in self.abi.functions() {
-            println!("Function name: {}", function.name);
-            println!("Inputs:");
-            for input in &function.inputs {
-                println!("  - {}: {}", input.name, input.kind);
-            }
-            println!("Outputs:");
-            for output in &function.outputs {
-                println!("  - {}: {}", output.name, output.kind);
+    /// Transaction calling `function`, ready for gas estimation and signing.
+    pub fn transaction(&self, function: &Function, args: &[String]) -> Result<TransactionRequest> {
+        Ok(TransactionRequest::new()
+            .to(self.address)
+            .data(self.encode(function, args)?))
+    }
+
+    /// Runs `function` through `eth_call` and decodes the returned values.
+    pub async fn call(
+        &self,
+        function: &Function,
+        args: &[String],
+        block: Option<BlockId>,
+    ) -> Result<Value> {
+        let tx: TypedTransaction = self.transaction(function, args)?.into();
+
+        let output = match self.contract.client().call(&tx, block).await {
+            Result::Ok(output) => output,
+            Err(err) => {
+                return match err.as_error_response().and_then(|err| err.as_revert_data()) {
+                    Some(data) => Err(eyre!("reverted: {}", self.decode_revert(&data))),
+                    None => Err(eyre!("{err}")),
+                }
             }
-            println!();
+        };
+
+        let tokens = function.decode_output(&output)?;
+        Ok(outputs_to_json(function, tokens))
+    }
+
+    pub fn decode_revert(&self, data: &[u8]) -> String {
+        decode_revert(&self.abi, data)
+    }
+}
+
+/// Reads a plain ABI array or a Foundry/Hardhat artifact with an `abi` field.
+pub fn load_abi(file_path: &str) -> Result<Abi> {
+    let json: Value = serde_json::from_str(&fs::read_to_string(file_path)?)?;
+    let abi = match json {
+        Value::Object(mut artifact) => artifact
+            .remove("abi")
+            .ok_or_else(|| eyre!("{file_path} has no abi field"))?,
+        abi => abi,
+    };
+
+    Ok(serde_json::from_value(abi)?)
+}
+
+/// Readable form of revert data: `Error(string)`, `Panic(uint256)` or a
+/// custom error from the ABI, raw hex otherwise.
+pub fn decode_revert(abi: &Abi, data: &[u8]) -> String {
+    if data.len() < 4 {
+        return match data.is_empty() {
+            true => "without reason".to_string(),
+            false => hex::encode_prefixed(data),
+        };
+    }
+    let (selector, payload) = data.split_at(4);
+
+    if selector == ERROR_SELECTOR {
+        if let Result::Ok(reason) = String::decode(payload) {
+            return reason;
+        }
+    }
+    if selector == PANIC_SELECTOR {
+        if let Result::Ok(code) = U256::decode(payload) {
+            return format!("panic 0x{code:x}");
         }
     }
 
-    pub fn print_all_contract_events(&self) {
-        for event in self.abi.events() {
-            println!("Event name: {}", event.name);
-            println!("Inputs:");
-            for evt in &event.inputs {
-                println!("  - {}: {}", evt.name, evt.kind);
-                println!("      indexed - {}", evt.indexed);
-            }
+    for error in abi.errors() {
+        if error.signature().as_bytes()[..4] != *selector {
+            continue;
+        }
+        let types: Vec<ParamType> = error
+            .inputs
+            .iter()
+            .map(|param| param.kind.clone())
+            .collect();
+        if let Result::Ok(tokens) = abi::decode(&types, payload) {
+            let args: Vec<String> = tokens.into_iter().map(|token| token.to_string()).collect();
+            return format!("{}({})", error.name, args.join(", "));
+        }
+    }
+
+    hex::encode_prefixed(data)
+}
+
+/// Parses `args` by the ABI types of `params`, arrays as `[a,b]` and tuples as `(a,b)`.
+fn tokenize(params: &[Param], args: &[String], what: &str) -> Result<Vec<Token>> {
+    if args.len() != params.len() {
+        return Err(eyre!(
+            "{what} takes {} arguments, got {}",
+            params.len(),
+            args.len()
+        ));
+    }
+
+    params
+        .iter()
+        .zip(args)
+        .map(|(param, arg)| {
+            LenientTokenizer::tokenize(&param.kind, arg)
+                .map_err(|err| eyre!("argument {}: {err}", param.name))
+        })
+        .collect()
+}
+
+/// `name(type,...)` without outputs, the form overloads are told apart by.
+pub fn input_signature(function: &Function) -> String {
+    let inputs: Vec<String> = function
+        .inputs
+        .iter()
+        .map(|param| param.kind.to_string())
+        .collect();
+    format!("{}({})", function.name, inputs.join(","))
+}
+
+/// Decoded return values, keyed by output name when every output has one.
+pub fn outputs_to_json(function: &Function, tokens: Vec<Token>) -> Value {
+    let named = function
+        .outputs
+        .iter()
+        .all(|output| !output.name.is_empty());
+    let mut values: Vec<Value> = tokens.into_iter().map(token_to_json).collect();
+
+    match (named, values.len()) {
+        (_, 0) => Value::Null,
+        (false, 1) => values.remove(0),
+        (true, _) => Value::Object(
+            function
+                .outputs
+                .iter()
+                .map(|output| output.name.clone())
+                .zip(values)
+                .collect::<Map<String, Value>>(),
+        ),
+        (false, _) => Value::Array(values),
+    }
+}
+
+/// Numbers become decimal strings so large values survive JSON parsers.
+pub fn token_to_json(token: Token) -> Value {
+    match token {
+        Token::Address(address) => json!(to_checksum(&address, None)),
+        Token::FixedBytes(bytes) | Token::Bytes(bytes) => json!(hex::encode_prefixed(bytes)),
+        Token::Int(value) => json!(I256::from_raw(value).to_string()),
+        Token::Uint(value) => json!(value.to_string()),
+        Token::Bool(value) => json!(value),
+        Token::String(value) => json!(value),
+        Token::FixedArray(tokens) | Token::Array(tokens) | Token::Tuple(tokens) => {
+            Value::Array(tokens.into_iter().map(token_to_json).collect())
         }
     }
 }
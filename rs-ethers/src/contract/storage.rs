@@ -0,0 +1,734 @@
+use super::token_to_json;
+use crate::client::EtherClient;
+use ethers::{
+    abi::{self, param_type::Reader, token::LenientTokenizer, token::Tokenizer, ParamType, Token},
+    types::{Address, H256, I256, U256},
+    utils::{hex, keccak256},
+};
+use eyre::{eyre, Ok, Result};
+use serde_json::{json, Map, Value};
+use std::{collections::HashMap, fs, future::Future, pin::Pin};
+
+/// `keccak256("eip1967.proxy.implementation") - 1`
+pub const EIP1967_IMPLEMENTATION_SLOT: &str =
+    "0x360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc";
+/// `keccak256("eip1967.proxy.admin") - 1`
+pub const EIP1967_ADMIN_SLOT: &str =
+    "0xb53127684a568b3173ae13b9f8a6016e243e63b6e8ee1178d6a717850b5d6103";
+/// `keccak256("eip1967.proxy.beacon") - 1`
+pub const EIP1967_BEACON_SLOT: &str =
+    "0xa3f0ad74e5423aebfd80d3ef4346578335a9a72aeaee59ff6cb3582b35133d50";
+
+type ReadFuture<'a> = Pin<Box<dyn Future<Output = Result<Value>> + 'a>>;
+
+/// How a type is laid out in storage, after the `encoding` field of solc's storage layout.
+#[derive(Debug, Clone)]
+pub enum StorageType {
+    /// Elementary value, possibly packed with neighbours in one slot.
+    Value {
+        kind: ParamType,
+        size: usize,
+    },
+    /// `string` or `bytes`: short values inline, long ones at `keccak(slot)`.
+    Bytes {
+        is_string: bool,
+    },
+    /// Values live at `keccak(key . slot)`, `key_kind` tells how keys are encoded.
+    Mapping {
+        key_kind: ParamType,
+        value: Box<StorageType>,
+    },
+    /// Length in the slot, elements from `keccak(slot)`.
+    DynamicArray {
+        base: Box<StorageType>,
+    },
+    StaticArray {
+        base: Box<StorageType>,
+        length: usize,
+    },
+    Struct {
+        members: Vec<Variable>,
+        size: usize,
+    },
+}
+
+impl StorageType {
+    /// A packed value takes 1 to 32 bytes of its slot.
+    fn value(kind: ParamType, size: usize) -> Result<StorageType> {
+        if !(1..=32).contains(&size) {
+            return Err(eyre!("{kind} takes {size} bytes, a slot holds 1 to 32"));
+        }
+        Ok(StorageType::Value { kind, size })
+    }
+
+    /// Bytes the type occupies, whole slots for everything that is not packed.
+    fn size(&self) -> usize {
+        match self {
+            StorageType::Value { size, .. } => *size,
+            StorageType::Struct { size, .. } => *size,
+            StorageType::StaticArray { base, length } => slots_for(base, *length) * 32,
+            _ => 32,
+        }
+    }
+
+    /// Parses a type as written in Solidity: `uint128`, `string`, `address[]`,
+    /// `uint8[4]` or `mapping(address => mapping(address => uint256))`.
+    pub fn parse(label: &str) -> Result<StorageType> {
+        let label = label.trim();
+        // ethabi reads any unknown name as an enum, an empty one included.
+        if label.is_empty() {
+            return Err(eyre!("missing type"));
+        }
+
+        if let Some(inner) = label
+            .strip_prefix("mapping(")
+            .and_then(|inner| inner.strip_suffix(')'))
+        {
+            let (key, value) = inner
+                .split_once("=>")
+                .ok_or_else(|| eyre!("mapping without => in {label}"))?;
+            return Ok(StorageType::Mapping {
+                key_kind: Reader::read(key.trim())?,
+                value: Box::new(StorageType::parse(value)?),
+            });
+        }
+
+        if let Some(base) = label.strip_suffix("[]") {
+            return Ok(StorageType::DynamicArray {
+                base: Box::new(StorageType::parse(base)?),
+            });
+        }
+        if let Some((base, length)) = split_static_array(label) {
+            return Ok(StorageType::StaticArray {
+                base: Box::new(StorageType::parse(base)?),
+                length: length.parse()?,
+            });
+        }
+
+        match label {
+            "string" => Ok(StorageType::Bytes { is_string: true }),
+            "bytes" => Ok(StorageType::Bytes { is_string: false }),
+            _ => {
+                let kind = Reader::read(label)?;
+                let size = match kind {
+                    ParamType::Address => 20,
+                    ParamType::Bool => 1,
+                    ParamType::Uint(bits) | ParamType::Int(bits) => bits / 8,
+                    ParamType::FixedBytes(size) => size,
+                    _ => return Err(eyre!("{label} cannot be stored as a value")),
+                };
+                StorageType::value(kind, size)
+            }
+        }
+    }
+}
+
+/// A state variable, or a struct member relative to the struct's first slot.
+#[derive(Debug, Clone)]
+pub struct Variable {
+    pub label: String,
+    pub slot: U256,
+    /// Byte offset from the right end of the slot, for packed values.
+    pub offset: usize,
+    pub ty: StorageType,
+}
+
+impl Variable {
+    /// Checks that a packed value fits in its slot, only values share slots.
+    fn new(label: &str, slot: &Value, offset: &Value, ty: StorageType) -> Result<Variable> {
+        let offset = number(offset).unwrap_or_default();
+        let end = match &ty {
+            StorageType::Value { size, .. } => offset.saturating_add(U256::from(*size)),
+            _ => offset,
+        };
+        let fits = match &ty {
+            StorageType::Value { .. } => end <= U256::from(32),
+            _ => offset.is_zero(),
+        };
+        if !fits {
+            return Err(eyre!("{label} at offset {offset} does not fit in its slot"));
+        }
+
+        Ok(Variable {
+            label: label.to_string(),
+            slot: number(slot)?,
+            offset: offset.as_usize(),
+            ty,
+        })
+    }
+}
+
+/// Variables of a contract, from solc's `storageLayout` or a manual spec.
+pub struct StorageLayout {
+    pub variables: Vec<Variable>,
+}
+
+impl StorageLayout {
+    /// Reads a solc `storageLayout` object (also nested in an artifact), or a
+    /// manual spec: a list of `{"label", "slot", "offset"?, "type"}` with Solidity types.
+    pub fn load(path: &str) -> Result<StorageLayout> {
+        let json: Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+        let layout = match json.get("storageLayout") {
+            Some(layout) => layout.clone(),
+            None => json,
+        };
+
+        let variables = match &layout {
+            Value::Array(entries) => entries
+                .iter()
+                .map(|entry| {
+                    Variable::new(
+                        entry["label"].as_str().unwrap_or_default(),
+                        &entry["slot"],
+                        &entry["offset"],
+                        StorageType::parse(entry["type"].as_str().unwrap_or_default())?,
+                    )
+                })
+                .collect::<Result<Vec<Variable>>>()?,
+            Value::Object(_) => {
+                let types = layout["types"]
+                    .as_object()
+                    .ok_or_else(|| eyre!("{path} has no types in its storage layout"))?;
+                let storage = layout["storage"].as_array().cloned().unwrap_or_default();
+                solc_variables(&storage, types)?
+            }
+            _ => return Err(eyre!("{path} is not a storage layout")),
+        };
+
+        Ok(StorageLayout { variables })
+    }
+}
+
+fn solc_variables(storage: &[Value], types: &Map<String, Value>) -> Result<Vec<Variable>> {
+    storage
+        .iter()
+        .map(|entry| {
+            Variable::new(
+                entry["label"].as_str().unwrap_or_default(),
+                &entry["slot"],
+                &entry["offset"],
+                solc_type(entry["type"].as_str().unwrap_or_default(), types)?,
+            )
+        })
+        .collect()
+}
+
+fn solc_type(id: &str, types: &Map<String, Value>) -> Result<StorageType> {
+    let info = types
+        .get(id)
+        .ok_or_else(|| eyre!("type {id} missing from the storage layout"))?;
+    let label = info["label"].as_str().unwrap_or_default();
+    let size = number(&info["numberOfBytes"])?;
+    if size > U256::from(u32::MAX) {
+        return Err(eyre!("type {id} takes {size} bytes"));
+    }
+    let size = size.as_usize();
+
+    let ty = match info["encoding"].as_str().unwrap_or_default() {
+        "bytes" => StorageType::Bytes {
+            is_string: label == "string",
+        },
+        "mapping" => {
+            let key = types
+                .get(info["key"].as_str().unwrap_or_default())
+                .and_then(|key| key["label"].as_str())
+                .unwrap_or_default();
+            StorageType::Mapping {
+                key_kind: elementary_kind(key)?,
+                value: Box::new(solc_type(
+                    info["value"].as_str().unwrap_or_default(),
+                    types,
+                )?),
+            }
+        }
+        "dynamic_array" => StorageType::DynamicArray {
+            base: Box::new(solc_type(info["base"].as_str().unwrap_or_default(), types)?),
+        },
+        _ if info.get("members").is_some() => StorageType::Struct {
+            members: solc_variables(info["members"].as_array().unwrap_or(&Vec::new()), types)?,
+            size,
+        },
+        _ if info.get("base").is_some() => {
+            let (_, length) = split_static_array(label)
+                .ok_or_else(|| eyre!("type {id} has base but no array length in {label:?}"))?;
+            StorageType::StaticArray {
+                base: Box::new(solc_type(info["base"].as_str().unwrap_or_default(), types)?),
+                length: length.parse()?,
+            }
+        }
+        _ => StorageType::value(elementary_kind(label)?, size)?,
+    };
+    Ok(ty)
+}
+
+/// `uint8[4]` as `("uint8", "4")`, `None` when the label does not end in a static length.
+fn split_static_array(label: &str) -> Option<(&str, &str)> {
+    label.strip_suffix(']')?.rsplit_once('[')
+}
+
+/// ABI type behind a solc type label, enums are stored as uints and contracts as addresses.
+fn elementary_kind(label: &str) -> Result<ParamType> {
+    if label.starts_with("contract ") || label == "address payable" {
+        return Ok(ParamType::Address);
+    }
+    if label.starts_with("enum ") {
+        return Ok(ParamType::Uint(8));
+    }
+    if label == "string" {
+        return Ok(ParamType::String);
+    }
+    Ok(Reader::read(label)?)
+}
+
+fn number(value: &Value) -> Result<U256> {
+    match value {
+        Value::Number(number) => Ok(U256::from(number.as_u64().unwrap_or_default())),
+        Value::String(text) => match text.strip_prefix("0x") {
+            Some(hex) => Ok(U256::from_str_radix(hex, 16)?),
+            None => Ok(U256::from_dec_str(text)?),
+        },
+        _ => Err(eyre!("expected a number, got {value}")),
+    }
+}
+
+/// Reads and decodes variables of `address` through `eth_getStorageAt`.
+pub struct StorageReader<'a> {
+    pub client: &'a EtherClient,
+    pub address: Address,
+    /// Mapping keys per variable label, each entry a `:` separated path for nested mappings.
+    pub keys: HashMap<String, Vec<String>>,
+    /// Elements read from each dynamic array.
+    pub max_items: usize,
+}
+
+impl StorageReader<'_> {
+    pub async fn read_layout(&self, layout: &StorageLayout) -> Result<Value> {
+        let mut values = Map::new();
+        for variable in &layout.variables {
+            let value = self
+                .read(
+                    &variable.label,
+                    &variable.ty,
+                    variable.slot,
+                    variable.offset,
+                )
+                .await?;
+            values.insert(variable.label.clone(), value);
+        }
+        Ok(Value::Object(values))
+    }
+
+    /// Implementation, admin and beacon addresses from the EIP-1967 proxy slots.
+    pub async fn read_proxy(&self) -> Result<Value> {
+        let mut proxy = Map::new();
+        for (name, slot) in [
+            ("implementation", EIP1967_IMPLEMENTATION_SLOT),
+            ("admin", EIP1967_ADMIN_SLOT),
+            ("beacon", EIP1967_BEACON_SLOT),
+        ] {
+            let word = self.word(U256::from_str_radix(slot, 16)?).await?;
+            let address = Address::from_slice(&word[12..]);
+            let value = match address.is_zero() {
+                true => Value::Null,
+                false => token_to_json(Token::Address(address)),
+            };
+            proxy.insert(name.to_string(), value);
+        }
+        Ok(Value::Object(proxy))
+    }
+
+    fn read<'a>(
+        &'a self,
+        label: &'a str,
+        ty: &'a StorageType,
+        slot: U256,
+        offset: usize,
+    ) -> ReadFuture<'a> {
+        Box::pin(async move {
+            match ty {
+                StorageType::Value { kind, size } => {
+                    let word = self.word(slot).await?;
+                    decode_value(kind, &word[32 - offset - size..32 - offset])
+                }
+                StorageType::Bytes { is_string } => self.read_bytes(slot, *is_string).await,
+                StorageType::Mapping { key_kind, value } => {
+                    let Some(paths) = self.keys.get(label) else {
+                        return Ok(json!({ "slot": slot_hex(slot), "keys": "none given" }));
+                    };
+
+                    let mut entries = Map::new();
+                    for path in paths {
+                        let (ty, slot) = mapping_slot(key_kind, value, slot, path)?;
+                        entries.insert(path.clone(), self.read(label, ty, slot, 0).await?);
+                    }
+                    Ok(Value::Object(entries))
+                }
+                StorageType::DynamicArray { base } => {
+                    let length = U256::from_big_endian(&self.word(slot).await?);
+                    let start = U256::from(keccak256(slot_bytes(slot)));
+                    let count = length.min(U256::from(self.max_items)).as_usize();
+                    let items = self.read_elements(label, base, start, count).await?;
+                    Ok(json!({ "length": length.to_string(), "items": items }))
+                }
+                StorageType::StaticArray { base, length } => Ok(Value::Array(
+                    self.read_elements(label, base, slot, *length).await?,
+                )),
+                StorageType::Struct { members, .. } => {
+                    let mut fields = Map::new();
+                    for member in members {
+                        let value = self
+                            .read(&member.label, &member.ty, slot + member.slot, member.offset)
+                            .await?;
+                        fields.insert(member.label.clone(), value);
+                    }
+                    Ok(Value::Object(fields))
+                }
+            }
+        })
+    }
+
+    /// Array elements from `start`, several per slot when they are small enough to pack.
+    async fn read_elements(
+        &self,
+        label: &str,
+        base: &StorageType,
+        start: U256,
+        count: usize,
+    ) -> Result<Vec<Value>> {
+        let size = base.size();
+        let mut items = Vec::with_capacity(count);
+        for index in 0..count {
+            let (slot, offset) = match size {
+                size if size <= 16 => {
+                    let per_slot = 32 / size;
+                    (index / per_slot, (index % per_slot) * size)
+                }
+                size => (index * size.div_ceil(32), 0),
+            };
+            items.push(self.read(label, base, start + slot, offset).await?);
+        }
+        Ok(items)
+    }
+
+    async fn read_bytes(&self, slot: U256, is_string: bool) -> Result<Value> {
+        let word = self.word(slot).await?;
+
+        let data = match bytes_slot(&word).map_err(|err| eyre!("slot {}: {err}", slot_hex(slot)))? {
+            BytesSlot::Inline(data) => data,
+            BytesSlot::Long(length) => {
+                let start = U256::from(keccak256(slot_bytes(slot)));
+                let mut data = Vec::with_capacity(length);
+                for index in 0..length.div_ceil(32) {
+                    data.extend_from_slice(&self.word(start + index).await?);
+                }
+                data.truncate(length);
+                data
+            }
+        };
+
+        Ok(match is_string {
+            true => json!(String::from_utf8_lossy(&data)),
+            false => json!(hex::encode_prefixed(data)),
+        })
+    }
+
+    async fn word(&self, slot: U256) -> Result<[u8; 32]> {
+        let value = self
+            .client
+            .get_slot_data(self.address, H256::from(slot_bytes(slot)))
+            .await?;
+        Ok(value.0)
+    }
+}
+
+/// What the slot of a `string` or `bytes` holds.
+#[derive(Debug, PartialEq)]
+enum BytesSlot {
+    /// Up to 31 bytes, stored with the length in the slot.
+    Inline(Vec<u8>),
+    /// Length of data stored from `keccak(slot)`.
+    Long(usize),
+}
+
+/// The lowest bit tells short (inline, length * 2) from long (length * 2 + 1)
+/// values. Slots that fit neither do not hold a `string` or `bytes`.
+fn bytes_slot(word: &[u8; 32]) -> Result<BytesSlot> {
+    if word[31] & 1 == 0 {
+        let length = (word[31] / 2) as usize;
+        if length > 31 || word[length..31].iter().any(|byte| *byte != 0) {
+            return Err(eyre!(
+                "{} is not a short string or bytes",
+                hex::encode_prefixed(word)
+            ));
+        }
+        return Ok(BytesSlot::Inline(word[..length].to_vec()));
+    }
+
+    let length = U256::from_big_endian(word) / 2;
+    if length < U256::from(32) || length > U256::from(u32::MAX) {
+        return Err(eyre!(
+            "{} is not the length of a long string or bytes",
+            hex::encode_prefixed(word)
+        ));
+    }
+    Ok(BytesSlot::Long(length.as_usize()))
+}
+
+/// Walks `path` (keys separated by `:`) through nested mappings to the value slot.
+fn mapping_slot<'a>(
+    key_kind: &ParamType,
+    value: &'a StorageType,
+    slot: U256,
+    path: &str,
+) -> Result<(&'a StorageType, U256)> {
+    let (key, rest) = match path.split_once(':') {
+        Some((key, rest)) => (key, Some(rest)),
+        None => (path, None),
+    };
+
+    let slot = U256::from(keccak256(
+        [encode_key(key_kind, key)?, slot_bytes(slot).to_vec()].concat(),
+    ));
+    match (value, rest) {
+        (StorageType::Mapping { key_kind, value }, Some(rest)) => {
+            mapping_slot(key_kind, value, slot, rest)
+        }
+        (_, None) => Ok((value, slot)),
+        (_, Some(_)) => Err(eyre!("too many keys in {path}")),
+    }
+}
+
+/// Value types are padded to 32 bytes, strings and bytes are hashed as they are.
+fn encode_key(kind: &ParamType, key: &str) -> Result<Vec<u8>> {
+    let token = LenientTokenizer::tokenize(kind, key)?;
+    Ok(match token {
+        Token::String(text) => text.into_bytes(),
+        Token::Bytes(bytes) => bytes,
+        Token::FixedBytes(mut bytes) => {
+            bytes.resize(32, 0);
+            bytes
+        }
+        token => abi::encode(&[token]),
+    })
+}
+
+/// Decodes a value cut out of its slot, `bytes` holds exactly the value's bytes.
+fn decode_value(kind: &ParamType, bytes: &[u8]) -> Result<Value> {
+    let token = match kind {
+        ParamType::Address => Token::Address(Address::from_slice(&bytes[bytes.len() - 20..])),
+        ParamType::Bool => Token::Bool(bytes.iter().any(|byte| *byte != 0)),
+        ParamType::Uint(_) => Token::Uint(U256::from_big_endian(bytes)),
+        ParamType::Int(_) => {
+            // Sign extend the packed value to 256 bits.
+            let fill = match bytes.first() {
+                Some(byte) if byte & 0x80 != 0 => 0xff,
+                _ => 0,
+            };
+            let mut word = [fill; 32];
+            word[32 - bytes.len()..].copy_from_slice(bytes);
+            return Ok(json!(
+                I256::from_raw(U256::from_big_endian(&word)).to_string()
+            ));
+        }
+        ParamType::FixedBytes(_) => Token::FixedBytes(bytes.to_vec()),
+        _ => return Err(eyre!("cannot decode {kind} from a storage slot")),
+    };
+    Ok(token_to_json(token))
+}
+
+fn slots_for(base: &StorageType, length: usize) -> usize {
+    match base.size() {
+        size if size <= 16 => length.div_ceil(32 / size),
+        size => length * size.div_ceil(32),
+    }
+}
+
+fn slot_bytes(slot: U256) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    slot.to_big_endian(&mut bytes);
+    bytes
+}
+
+fn slot_hex(slot: U256) -> String {
+    hex::encode_prefixed(slot_bytes(slot))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(hex: &str) -> [u8; 32] {
+        H256::from_slice(&hex::decode(hex).unwrap()).0
+    }
+
+    #[test]
+    fn parses_solidity_types() {
+        let ty = StorageType::parse("mapping(address => mapping(address => uint256))").unwrap();
+        let StorageType::Mapping { key_kind, value } = ty else {
+            panic!("not a mapping: {ty:?}");
+        };
+        assert_eq!(key_kind, ParamType::Address);
+        assert!(matches!(*value, StorageType::Mapping { .. }));
+
+        assert!(matches!(
+            StorageType::parse(" uint128 ").unwrap(),
+            StorageType::Value {
+                kind: ParamType::Uint(128),
+                size: 16
+            }
+        ));
+        assert!(matches!(
+            StorageType::parse("string").unwrap(),
+            StorageType::Bytes { is_string: true }
+        ));
+        assert!(matches!(
+            StorageType::parse("address[]").unwrap(),
+            StorageType::DynamicArray { .. }
+        ));
+        let StorageType::StaticArray { base, length } = StorageType::parse("uint8[4][2]").unwrap()
+        else {
+            panic!("not a static array");
+        };
+        assert_eq!(length, 2);
+        assert!(matches!(*base, StorageType::StaticArray { length: 4, .. }));
+
+        for bad in ["", "[]", "uint8[x]", "mapping(address)", "tuple", "uint8["] {
+            assert!(StorageType::parse(bad).is_err(), "{bad:?} parsed");
+        }
+    }
+
+    #[test]
+    fn rejects_values_outside_their_slot() {
+        let ty = || StorageType::parse("uint128").unwrap();
+        assert!(Variable::new("a", &json!("0"), &json!(16), ty()).is_ok());
+        assert!(Variable::new("a", &json!("0"), &json!(17), ty()).is_err());
+        let string = StorageType::parse("string").unwrap();
+        assert!(Variable::new("s", &json!("1"), &json!(1), string).is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_static_array_labels() {
+        let types = json!({
+            "t_uint8": { "encoding": "inplace", "label": "uint8", "numberOfBytes": "1" },
+            "t_array": { "encoding": "inplace", "label": "", "base": "t_uint8", "numberOfBytes": "32" },
+        });
+        assert!(solc_type("t_array", types.as_object().unwrap()).is_err());
+    }
+
+    #[test]
+    fn mapping_slots_hash_key_and_slot() {
+        let uint = StorageType::parse("uint256").unwrap();
+
+        // keccak256 of 64 zero bytes: key 0 in a mapping at slot 0.
+        let (_, slot) = mapping_slot(&ParamType::Uint(256), &uint, U256::zero(), "0").unwrap();
+        assert_eq!(
+            slot_hex(slot),
+            "0xad3228b676f7d3cd4284a5443f17f1962b36e491b30a40b2405849e597ba5fb5"
+        );
+
+        let nested = StorageType::parse("mapping(address => uint256)").unwrap();
+        let owner = "0x00000000000000000000000000000000000000aa";
+        let spender = "0x00000000000000000000000000000000000000bb";
+        let (ty, slot) = mapping_slot(
+            &ParamType::Address,
+            &nested,
+            U256::from(1),
+            &format!("{owner}:{spender}"),
+        )
+        .unwrap();
+        assert!(matches!(ty, StorageType::Value { .. }));
+        let inner = keccak256(
+            [
+                encode_key(&ParamType::Address, owner).unwrap(),
+                slot_bytes(1.into()).to_vec(),
+            ]
+            .concat(),
+        );
+        let expected = keccak256(
+            [
+                encode_key(&ParamType::Address, spender).unwrap(),
+                inner.to_vec(),
+            ]
+            .concat(),
+        );
+        assert_eq!(slot, U256::from(expected));
+
+        assert!(mapping_slot(&ParamType::Uint(256), &uint, U256::zero(), "1:2").is_err());
+    }
+
+    #[test]
+    fn encodes_mapping_keys() {
+        let address = encode_key(
+            &ParamType::Address,
+            "0x00000000000000000000000000000000000000aa",
+        )
+        .unwrap();
+        assert_eq!(address.len(), 32);
+        assert_eq!(address[31], 0xaa);
+        assert_eq!(encode_key(&ParamType::String, "abc").unwrap(), b"abc");
+        let fixed = encode_key(&ParamType::FixedBytes(4), "0x12345678").unwrap();
+        assert_eq!(&fixed[..4], [0x12, 0x34, 0x56, 0x78]);
+        assert_eq!(fixed.len(), 32);
+    }
+
+    #[test]
+    fn decodes_packed_values() {
+        assert_eq!(
+            decode_value(&ParamType::Int(8), &[0xff]).unwrap(),
+            json!("-1")
+        );
+        assert_eq!(
+            decode_value(&ParamType::Int(16), &[0x80, 0x00]).unwrap(),
+            json!("-32768")
+        );
+        assert_eq!(
+            decode_value(&ParamType::Int(16), &[0x7f, 0xff]).unwrap(),
+            json!("32767")
+        );
+        assert_eq!(
+            decode_value(&ParamType::Uint(8), &[0xff]).unwrap(),
+            json!("255")
+        );
+        assert_eq!(
+            decode_value(&ParamType::Bool, &[0x01]).unwrap(),
+            json!(true)
+        );
+        assert!(decode_value(&ParamType::String, &[0x01]).is_err());
+    }
+
+    #[test]
+    fn counts_slots_of_static_arrays() {
+        let parse = |label| StorageType::parse(label).unwrap();
+        assert_eq!(slots_for(&parse("uint8"), 40), 2);
+        assert_eq!(slots_for(&parse("uint128"), 3), 2);
+        assert_eq!(slots_for(&parse("address"), 3), 3);
+        assert_eq!(slots_for(&parse("uint256"), 3), 3);
+        assert_eq!(slots_for(&parse("uint8[40]"), 2), 4);
+    }
+
+    #[test]
+    fn decodes_bytes_slots() {
+        // "abc", short: data left aligned, length * 2 in the lowest byte.
+        let short = word("6162630000000000000000000000000000000000000000000000000000000006");
+        assert_eq!(
+            bytes_slot(&short).unwrap(),
+            BytesSlot::Inline(b"abc".to_vec())
+        );
+
+        // 40 bytes, long: length * 2 + 1.
+        let long = word("0000000000000000000000000000000000000000000000000000000000000051");
+        assert_eq!(bytes_slot(&long).unwrap(), BytesSlot::Long(40));
+
+        // Not string slots: an even low byte of 66 or more, a short value with
+        // junk after it, a long length under 32 and one that does not fit in a usize.
+        for hex in [
+            "0000000000000000000000000000000000000000000000000000000000000042",
+            "61626300000000000000000000000000000000000000000000000000000000fe",
+            "6162630000000000000000000000000000000000000000000000000000000106",
+            "0000000000000000000000000000000000000000000000000000000000000003",
+            "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+        ] {
+            assert!(bytes_slot(&word(hex)).is_err(), "{hex} decoded");
+        }
+    }
+}
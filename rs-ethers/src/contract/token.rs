@@ -0,0 +1,133 @@
+use ethers::{
+    contract::abigen,
+    providers::Middleware,
+    types::{Address, Bytes, TransactionRequest, U256},
+    utils::{format_units, parse_units},
+};
+use eyre::{eyre, Ok, Result};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+abigen!(IERC20, "src/contract/IERC20.json");
+abigen!(IERC721, "src/contract/IERC721.json");
+
+/// ERC-20 token, amounts are read and written scaled by its decimals.
+pub struct Erc20<M> {
+    token: IERC20<M>,
+    pub decimals: u8,
+}
+
+impl<M: Middleware + 'static> Erc20<M> {
+    pub async fn new(client: Arc<M>, address: Address) -> Result<Erc20<M>> {
+        let token = IERC20::new(address, client);
+        let decimals = token
+            .decimals()
+            .call()
+            .await
+            .map_err(|err| eyre!("{address:?} does not look like an ERC-20 token: {err}"))?;
+
+        Ok(Erc20 { token, decimals })
+    }
+
+    /// Name and symbol are optional in ERC-20 and left null when the token lacks them.
+    pub async fn metadata(&self) -> Result<Value> {
+        let total_supply = self.token.total_supply().call().await?;
+
+        Ok(json!({
+            "address": self.token.address(),
+            "name": self.token.name().call().await.ok(),
+            "symbol": self.token.symbol().call().await.ok(),
+            "decimals": self.decimals,
+            "total_supply": self.format_amount(total_supply)?,
+            "total_supply_raw": total_supply.to_string(),
+        }))
+    }
+
+    pub async fn balance(&self, holder: Address) -> Result<Value> {
+        let balance = self.token.balance_of(holder).call().await?;
+
+        Ok(json!({
+            "holder": holder,
+            "balance": self.format_amount(balance)?,
+            "raw": balance.to_string(),
+        }))
+    }
+
+    pub async fn allowance(&self, owner: Address, spender: Address) -> Result<Value> {
+        let allowance = self.token.allowance(owner, spender).call().await?;
+
+        Ok(json!({
+            "owner": owner,
+            "spender": spender,
+            "allowance": self.format_amount(allowance)?,
+            "raw": allowance.to_string(),
+            "unlimited": allowance == U256::MAX,
+        }))
+    }
+
+    /// Decimal amount such as `1.5` in token units, `max` for an unlimited approval.
+    pub fn parse_amount(&self, amount: &str) -> Result<U256> {
+        if amount == "max" {
+            return Ok(U256::MAX);
+        }
+        Ok(parse_units(amount, self.decimals as u32)?.into())
+    }
+
+    pub fn format_amount(&self, raw: U256) -> Result<String> {
+        Ok(format_units(raw, self.decimals as u32)?)
+    }
+
+    pub fn transfer(&self, to: Address, amount: U256) -> TransactionRequest {
+        self.request(self.token.transfer(to, amount).calldata())
+    }
+
+    pub fn approve(&self, spender: Address, amount: U256) -> TransactionRequest {
+        self.request(self.token.approve(spender, amount).calldata())
+    }
+
+    fn request(&self, data: Option<Bytes>) -> TransactionRequest {
+        TransactionRequest::new()
+            .to(self.token.address())
+            .data(data.unwrap_or_default())
+    }
+}
+
+/// ERC-721 collection.
+pub struct Erc721<M> {
+    token: IERC721<M>,
+}
+
+impl<M: Middleware + 'static> Erc721<M> {
+    pub fn new(client: Arc<M>, address: Address) -> Erc721<M> {
+        Erc721 {
+            token: IERC721::new(address, client),
+        }
+    }
+
+    pub async fn owner_of(&self, token_id: U256) -> Result<Value> {
+        let owner = self.token.owner_of(token_id).call().await?;
+
+        Ok(json!({
+            "token_id": token_id.to_string(),
+            "owner": owner,
+        }))
+    }
+
+    /// `tokenURI` is part of the optional metadata extension, as are name and symbol.
+    pub async fn token_uri(&self, token_id: U256) -> Result<Value> {
+        let uri = self
+            .token
+            .token_uri(token_id)
+            .call()
+            .await
+            .map_err(|err| eyre!("tokenURI({token_id}) failed: {err}"))?;
+
+        Ok(json!({
+            "collection": self.token.address(),
+            "name": self.token.name().call().await.ok(),
+            "symbol": self.token.symbol().call().await.ok(),
+            "token_id": token_id.to_string(),
+            "token_uri": uri,
+        }))
+    }
+}
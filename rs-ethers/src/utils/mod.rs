@@ -0,0 +1,32 @@
+use std::{
+    error::Error,
+    io::{self, IsTerminal, Write},
+    process::{Command, Stdio},
+};
+
+/// Reads a secret from stdin, with echo turned off while stdin is a terminal.
+pub fn prompt_secret(prompt: &str) -> Result<String, Box<dyn Error>> {
+    eprint!("{prompt}: ");
+    io::stderr().flush()?;
+
+    let hide = io::stdin().is_terminal();
+    if hide {
+        set_echo(false);
+    }
+    let mut secret = String::new();
+    let read = io::stdin().read_line(&mut secret);
+    if hide {
+        set_echo(true);
+        eprintln!();
+    }
+    read?;
+
+    Ok(secret.trim_end_matches(['\r', '\n']).to_string())
+}
+
+fn set_echo(on: bool) {
+    let _ = Command::new("stty")
+        .arg(if on { "echo" } else { "-echo" })
+        .stdin(Stdio::inherit())
+        .status();
+}